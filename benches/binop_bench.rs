@@ -1,5 +1,5 @@
 use criterion::{black_box, criterion_group, criterion_main, Criterion, BenchmarkId};
-use perq::{Field, PowerSeries, Series, ShortSeq, MersP31};
+use perq::{Field, PowerSeries, Series, ShortSeq, FixedSeq, MersP31};
 use std::ops::{Add, Sub, Mul, Div};
 
 macro_rules! make_binop_func {
@@ -22,6 +22,15 @@ make_binop_func!(mul_test, mul, "1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16", "1,2,3
 make_binop_func!(div_test, div, "1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16", "1,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16");
 make_binop_func!(compose_test, compose, "0,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16", "0,2,3,4,5,6,7,8,9,10,11,12,13,14,15,16");
 
+// Long enough that the NTT path is taken
+fn long_mul_test(mxn: usize) {
+    let mut s: FixedSeq<MersP31, 2048> = (1..=2048u32).map(MersP31::from).collect();
+    let t: FixedSeq<MersP31, 2048> = (1..=2048u32).rev().map(MersP31::from).collect();
+    for _i in 0..mxn {
+        s = s * t;
+    }
+}
+
 macro_rules! bench_method {
     ( $g:expr, $meth:ident, $f:ident ) => {
         $g.bench_function(
@@ -42,6 +51,7 @@ fn bench_binop(c: &mut Criterion) {
     bench_method!(group, div, div_test);
     */
     bench_method!(group, compose, compose_test);
+    group.bench_function("long_mul_test", |b| b.iter(|| long_mul_test(black_box(10))));
 }
 
 criterion_group!(benches, bench_binop);
@@ -0,0 +1,106 @@
+use crate::error::PerqError;
+use crate::mathtypes::One;
+use rug::{Integer, Rational};
+use rug::ops::Pow;
+
+// Functions of exact constants, arguments are checked for count before the call
+pub type ArithFn = fn(&[Rational]) -> Result<Rational, PerqError>;
+
+pub fn lookup(name: &str) -> Option<ArithFn> {
+    match name {
+        "factorial" => Some(|a| Ok(Rational::from(factorial(&a[0])?))),
+        "binomial" => Some(|a| binomial(&a[0], &a[1])),
+        "gcd" => Some(|a| Ok(Rational::from(integer("gcd", &a[0])?.clone().gcd(integer("gcd", &a[1])?)))),
+        "lcm" => Some(|a| Ok(Rational::from(integer("lcm", &a[0])?.clone().lcm(integer("lcm", &a[1])?)))),
+        "floor" => Some(|a| Ok(a[0].clone().floor())),
+        "ceil" => Some(|a| Ok(a[0].clone().ceil())),
+        "numer" => Some(|a| Ok(Rational::from(a[0].numer()))),
+        "denom" => Some(|a| Ok(Rational::from(a[0].denom()))),
+        "mod" => Some(|a| modulo(&a[0], &a[1])),
+        _ => None
+    }
+}
+
+fn integer<'a>(name: &str, c: &'a Rational) -> Result<&'a Integer, PerqError> {
+    if !c.denom().is_one() {
+        return Err(PerqError::InvalidArgument(format!("{} takes integers.", name)));
+    }
+    Ok(c.numer())
+}
+
+fn small(name: &str, c: &Rational) -> Result<u32, PerqError> {
+    let Ok(n) = u32::try_from(integer(name, c)?) else {
+        return Err(PerqError::Domain(format!("{} argument {} is too large.", name, c)));
+    };
+    Ok(n)
+}
+
+pub fn factorial(n: &Rational) -> Result<Integer, PerqError> {
+    if *n < 0 {
+        return Err(PerqError::Domain("Factorial of a negative number.".to_string()));
+    }
+    Ok(Integer::factorial(small("factorial", n)?).into())
+}
+
+// Any rational n, so binomial(1/2, k) gives the coefficients of sqrt(1+x)
+pub fn binomial(n: &Rational, k: &Rational) -> Result<Rational, PerqError> {
+    if *k < 0 {
+        integer("binomial", k)?;
+        return Ok(Rational::new());
+    }
+    let k = small("binomial", k)?;
+    if n.denom().is_one() {
+        return Ok(Rational::from(n.numer().clone().binomial(k)));
+    }
+    let mut res = Rational::from(1);
+    for i in 0..k {
+        res *= Rational::from(n - i) / (i + 1);
+    }
+    Ok(res)
+}
+
+// Takes the sign of m, a - m*floor(a/m)
+pub fn modulo(a: &Rational, m: &Rational) -> Result<Rational, PerqError> {
+    if m.is_zero() {
+        return Err(PerqError::DivisionByZero);
+    }
+    let q = Rational::from(a / m).floor();
+    Ok(a - q * m)
+}
+
+// Exact power for any integer exponent the result fits in memory for. A rational
+// exponent p/q needs the base to be a perfect q-th power.
+pub fn pow(base: &Rational, exp: &Rational) -> Result<Rational, PerqError> {
+    let mut base = base.clone();
+    if !exp.denom().is_one() {
+        let Ok(q) = u32::try_from(exp.denom()) else {
+            return Err(PerqError::Domain("Root degree is too large.".to_string()));
+        };
+        if base < 0 && q % 2 == 0 {
+            return Err(PerqError::Domain(format!("Even root of negative number {}.", base)));
+        }
+        let num = base.numer().clone().root(q);
+        let den = base.denom().clone().root(q);
+        if Rational::from((num.clone(), den.clone())).pow(q) != base {
+            return Err(PerqError::Domain(format!("{} is not a perfect power of degree {}.", base, q)));
+        }
+        base = Rational::from((num, den));
+    }
+    let p = exp.numer();
+    if base.is_zero() {
+        return match p.cmp0() {
+            std::cmp::Ordering::Less => Err(PerqError::DivisionByZero),
+            std::cmp::Ordering::Equal => Ok(Rational::from(1)),
+            std::cmp::Ordering::Greater => Ok(base)
+        };
+    }
+    // Only 1 and -1 keep a bounded size for huge exponents
+    if base == 1 || base == -1 {
+        return Ok(if p.is_even() { Rational::from(1) } else { base });
+    }
+    let Ok(n) = u32::try_from(p.clone().abs()) else {
+        return Err(PerqError::Domain(format!("Power {} of {} is too large.", p, base)));
+    };
+    let res = base.pow(n);
+    Ok(if p.cmp0().is_lt() { res.recip() } else { res })
+}
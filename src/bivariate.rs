@@ -0,0 +1,433 @@
+use crate::{Field, PowerSeries};
+use std::ops::{Add, Sub, Mul, Div, Neg, AddAssign, SubAssign, MulAssign};
+use std::cmp::min;
+use std::iter::zip;
+
+// Series in x and y, coefficient (i, j) belongs to x^i y^j and is known for
+// i below rows and j below cols. Results are truncated to the smaller accuracy
+// in each variable, like the univariate series.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BiSeries<T: Field> {
+    rows: usize,
+    cols: usize,
+    dat: Vec<T>
+}
+
+impl<T: Field> std::ops::Index<(usize,usize)> for BiSeries<T> {
+    type Output = T;
+    fn index(&self, index: (usize, usize)) -> &T {
+        &self.dat[index.0 * self.cols + index.1]
+    }
+}
+
+impl<T: Field> std::ops::IndexMut<(usize,usize)> for BiSeries<T> {
+    fn index_mut(&mut self, index: (usize, usize)) -> &mut T {
+        &mut self.dat[index.0 * self.cols + index.1]
+    }
+}
+
+impl<T: Field> Add<&BiSeries<T>> for &BiSeries<T> {
+    type Output = BiSeries<T>;
+
+    fn add(self, other: &BiSeries<T>) -> BiSeries<T> {
+        let mut res = self.truncated(other.rows, other.cols);
+        res += other;
+        res
+    }
+}
+
+impl<T: Field> AddAssign<&BiSeries<T>> for BiSeries<T> {
+    fn add_assign(&mut self, other: &BiSeries<T>) {
+        self.limit_accuracy(other.rows, other.cols);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                self[(i, j)] += &other[(i, j)];
+            }
+        }
+    }
+}
+
+impl<T: Field> Sub<&BiSeries<T>> for &BiSeries<T> {
+    type Output = BiSeries<T>;
+
+    fn sub(self, other: &BiSeries<T>) -> BiSeries<T> {
+        let mut res = self.truncated(other.rows, other.cols);
+        res -= other;
+        res
+    }
+}
+
+impl<T: Field> SubAssign<&BiSeries<T>> for BiSeries<T> {
+    fn sub_assign(&mut self, other: &BiSeries<T>) {
+        self.limit_accuracy(other.rows, other.cols);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                self[(i, j)] -= &other[(i, j)];
+            }
+        }
+    }
+}
+
+impl<T: Field> Neg for &BiSeries<T> {
+    type Output = BiSeries<T>;
+
+    fn neg(self) -> BiSeries<T> {
+        BiSeries::<T> {
+            rows: self.rows,
+            cols: self.cols,
+            dat: self.dat.iter().map(|x| -x.clone()).collect()
+        }
+    }
+}
+
+impl<T: Field> Mul<&BiSeries<T>> for &BiSeries<T> {
+    type Output = BiSeries<T>;
+
+    fn mul(self, other: &BiSeries<T>) -> BiSeries<T> {
+        let rows = min(self.rows, other.rows);
+        let cols = min(self.cols, other.cols);
+        let mut res = BiSeries::<T>::new(rows, cols);
+        for i1 in 0..rows {
+            for j1 in 0..cols {
+                if self[(i1, j1)].is_zero() { continue; }
+                for i2 in 0..rows - i1 {
+                    for j2 in 0..cols - j1 {
+                        res[(i1 + i2, j1 + j2)] += self[(i1, j1)].clone() * &other[(i2, j2)];
+                    }
+                }
+            }
+        }
+        res
+    }
+}
+
+impl<T: Field> MulAssign<&BiSeries<T>> for BiSeries<T> {
+    fn mul_assign(&mut self, other: &BiSeries<T>) {
+        *self = &*self * other;
+    }
+}
+
+impl<T: Field> Div<&BiSeries<T>> for &BiSeries<T> {
+    type Output = BiSeries<T>;
+
+    fn div(self, other: &BiSeries<T>) -> BiSeries<T> {
+        Mul::mul(self, &other.recip())
+    }
+}
+
+impl<T: Field + std::fmt::Display> std::fmt::Display for BiSeries<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut res = String::new();
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                let c = &self[(i, j)];
+                if c.is_zero() { continue; }
+                let c = c.to_string();
+                if !res.is_empty() && !c.starts_with('-') { res += "+"; }
+                if i + j == 0 || (c != "1" && c != "-1") {
+                    res += &c;
+                } else if c == "-1" {
+                    res += "-";
+                }
+                if i == 1 { res += "x"; }
+                if i > 1 { res += &format!("x^{}", i); }
+                if j == 1 { res += "y"; }
+                if j > 1 { res += &format!("y^{}", j); }
+            }
+        }
+        write!(f, "{}", if res.is_empty() { "0" } else { &res })
+    }
+}
+
+impl<T: Field> BiSeries<T> {
+    pub fn new(rows: usize, cols: usize) -> Self {
+        BiSeries::<T> {
+            rows,
+            cols,
+            dat: vec![T::zero(); rows * cols]
+        }
+    }
+
+    pub fn one(rows: usize, cols: usize) -> Self {
+        let mut res = Self::new(rows, cols);
+        if rows > 0 && cols > 0 {
+            res[(0, 0)] = T::one();
+        }
+        res
+    }
+
+    pub fn x(rows: usize, cols: usize) -> Self {
+        let mut res = Self::new(rows, cols);
+        if rows > 1 && cols > 0 {
+            res[(1, 0)] = T::one();
+        }
+        res
+    }
+
+    pub fn y(rows: usize, cols: usize) -> Self {
+        let mut res = Self::new(rows, cols);
+        if rows > 0 && cols > 1 {
+            res[(0, 1)] = T::one();
+        }
+        res
+    }
+
+    // A univariate series in x, known to every power of y below cols
+    pub fn from_x<S: PowerSeries<Coeff = T>>(s: &S, cols: usize) -> Self {
+        let mut res = Self::new(s.accuracy(), cols);
+        if cols > 0 {
+            for i in 0..res.rows {
+                res[(i, 0)] = s[i].clone();
+            }
+        }
+        res
+    }
+
+    // A univariate series in y, known to every power of x below rows
+    pub fn from_y<S: PowerSeries<Coeff = T>>(s: &S, rows: usize) -> Self {
+        let mut res = Self::new(rows, s.accuracy());
+        if rows > 0 {
+            for j in 0..res.cols {
+                res[(0, j)] = s[j].clone();
+            }
+        }
+        res
+    }
+
+    // Known powers of x and of y
+    pub fn accuracy(&self) -> (usize, usize) {
+        (self.rows, self.cols)
+    }
+
+    pub fn limit_accuracy(&mut self, rows: usize, cols: usize) {
+        if rows >= self.rows && cols >= self.cols {
+            return;
+        }
+        *self = self.truncated(rows, cols);
+    }
+
+    pub fn truncated(&self, rows: usize, cols: usize) -> Self {
+        let rows = min(rows, self.rows);
+        let cols = min(cols, self.cols);
+        let mut res = Self::new(rows, cols);
+        for i in 0..rows {
+            for j in 0..cols {
+                res[(i, j)] = self[(i, j)].clone();
+            }
+        }
+        res
+    }
+
+    // Coefficient of x^i as a series in y
+    pub fn row<S: PowerSeries<Coeff = T>>(&self, i: usize) -> S {
+        (0..self.cols).map(|j| self[(i, j)].clone()).collect()
+    }
+
+    // Coefficient of y^j as a series in x
+    pub fn col<S: PowerSeries<Coeff = T>>(&self, j: usize) -> S {
+        (0..self.rows).map(|i| self[(i, j)].clone()).collect()
+    }
+
+    pub fn transpose(&self) -> Self {
+        let mut res = Self::new(self.cols, self.rows);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                res[(j, i)] = self[(i, j)].clone();
+            }
+        }
+        res
+    }
+
+    pub fn derive_x(&self) -> Self {
+        let mut res = Self::new(self.rows.saturating_sub(1), self.cols);
+        for i in 0..res.rows {
+            for j in 0..res.cols {
+                res[(i, j)] = self[(i + 1, j)].clone() * T::from((i + 1) as u32);
+            }
+        }
+        res
+    }
+
+    pub fn derive_y(&self) -> Self {
+        self.transpose().derive_x().transpose()
+    }
+
+    pub fn integrate_x(&self) -> Self {
+        let mut res = Self::new(self.rows + 1, self.cols);
+        for i in 1..res.rows {
+            for j in 0..res.cols {
+                res[(i, j)] = self[(i - 1, j)].clone() / T::from(i as u32);
+            }
+        }
+        res
+    }
+
+    pub fn integrate_y(&self) -> Self {
+        self.transpose().integrate_x().transpose()
+    }
+
+    // Multiplicative inverse, solved coefficient by coefficient from the constant term
+    pub fn recip(&self) -> Self {
+        assert!(!self[(0, 0)].is_zero());
+        let inv = T::one() / &self[(0, 0)];
+        let mut res = Self::new(self.rows, self.cols);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                let mut acc = if i + j == 0 { T::one() } else { T::zero() };
+                for k in 0..=i {
+                    for l in 0..=j {
+                        if k + l == 0 || self[(k, l)].is_zero() { continue; }
+                        acc -= self[(k, l)].clone() * &res[(i - k, j - l)];
+                    }
+                }
+                res[(i, j)] = acc * &inv;
+            }
+        }
+        res
+    }
+
+    // Applies x d/dx + y d/dy, which multiplies the coefficient of x^i y^j by i + j
+    fn euler_op(&self) -> Self {
+        let mut res = self.clone();
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                res[(i, j)] *= T::from((i + j) as u32);
+            }
+        }
+        res
+    }
+
+    // exp(a) for a(0, 0) = 0, from D exp(a) = D(a) exp(a) with D the Euler operator above
+    pub fn exp(&self) -> Self {
+        assert!(self[(0, 0)].is_zero());
+        let da = self.euler_op();
+        let mut res = Self::new(self.rows, self.cols);
+        for i in 0..self.rows {
+            for j in 0..self.cols {
+                if i + j == 0 {
+                    res[(0, 0)] = T::one();
+                    continue;
+                }
+                let mut acc = T::zero();
+                for k in 0..=i {
+                    for l in 0..=j {
+                        if da[(k, l)].is_zero() { continue; }
+                        acc += da[(k, l)].clone() * &res[(i - k, j - l)];
+                    }
+                }
+                res[(i, j)] = acc / T::from((i + j) as u32);
+            }
+        }
+        res
+    }
+
+    // log(1 + a) for a(0, 0) = 0, the Euler operator applied to it is D(a) / (1 + a)
+    pub fn log(&self) -> Self {
+        assert!(self[(0, 0)].is_zero());
+        let mut f = self.clone();
+        if f.rows > 0 && f.cols > 0 {
+            f[(0, 0)] = T::one();
+        }
+        let mut res = &self.euler_op() / &f;
+        for i in 0..res.rows {
+            for j in 0..res.cols {
+                if i + j > 0 {
+                    res[(i, j)] /= T::from((i + j) as u32);
+                }
+            }
+        }
+        res
+    }
+
+    // self(x(x, y), y(x, y)) where neither substitute has a constant term
+    pub fn compose(&self, x: &Self, y: &Self) -> Self {
+        assert!(x[(0, 0)].is_zero() && y[(0, 0)].is_zero());
+        let rows = min(x.rows, y.rows);
+        let cols = min(x.cols, y.cols);
+        let x = x.truncated(rows, cols);
+        let mut ypow = vec![Self::one(rows, cols)];
+        for _ in 1..self.cols {
+            let next = ypow.last().unwrap() * y;
+            ypow.push(next);
+        }
+        // Horner in x over the rows, each a combination of the powers of y
+        let mut res = Self::new(rows, cols);
+        for i in (0..self.rows).rev() {
+            res *= &x;
+            for (j, p) in ypow.iter().enumerate() {
+                if self[(i, j)].is_zero() { continue; }
+                zip(res.dat.iter_mut(), p.dat.iter()).for_each(|(r, v)| *r += self[(i, j)].clone() * v);
+            }
+        }
+        res
+    }
+
+    // f(self) for a univariate f, self(0, 0) = 0 when f is not a polynomial
+    pub fn apply<S: PowerSeries<Coeff = T>>(&self, f: &S) -> Self {
+        assert!(self[(0, 0)].is_zero());
+        // Powers past the total degree vanish
+        let terms = min(f.accuracy(), self.rows + self.cols);
+        let mut res = Self::new(self.rows, self.cols);
+        for k in (0..terms).rev() {
+            res *= self;
+            if res.rows > 0 && res.cols > 0 {
+                res[(0, 0)] += &f[k];
+            }
+        }
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{PowerSeries, Series, ShortSeq, ModIntP32};
+    use crate::bivariate::BiSeries;
+    use rug::Rational;
+
+    #[test]
+    fn test_bivariate() {
+        // 1/(1 - x(1 + y)) has the rows of Pascal's triangle
+        let x = BiSeries::<Rational>::x(8, 8);
+        let y = BiSeries::<Rational>::y(8, 8);
+        let one = BiSeries::<Rational>::one(8, 8);
+        let pascal = &one / &(&one - &(&x * &(&one + &y)));
+        let row: Series = pascal.row(4);
+        assert_eq!(row, "1,4,6,4,1,0,0,0".parse().unwrap());
+        let col: Series = pascal.col(2);
+        assert_eq!(col, "0,0,1,3,6,10,15,21".parse().unwrap());
+        assert_eq!(&pascal * &(&one - &(&x + &(&x * &y))), one);
+        // Substituting x(1 + y) into 1/(1 - x)
+        let geom = BiSeries::from_x(&Series::from_iter(vec![Rational::from(1); 8]), 8);
+        assert_eq!(geom.compose(&(&x + &(&x * &y)), &y), pascal);
+        let s: Series = Series::from_iter(vec![Rational::from(1); 8]);
+        assert_eq!(pascal.col::<Series>(0), s);
+        assert_eq!((&x * &y).to_string(), "xy");
+        assert_eq!((&(&x * &x) - &(&y + &(&y + &y))).to_string(), "-3y+x^2");
+    }
+
+    #[test]
+    fn test_bivariate_exp_log() {
+        // exp(y(e^x - 1)) counts set partitions by blocks
+        let x = BiSeries::<Rational>::x(7, 7);
+        let y = BiSeries::<Rational>::y(7, 7);
+        let ex = x.apply(&Series::expx(7));
+        let mut ex1 = ex.clone();
+        ex1[(0, 0)] = Rational::from(0);
+        let bell = (&y * &ex1).exp();
+        assert_eq!(bell, (&y * &ex1).apply(&Series::expx(7)));
+        let stirling: Series = bell.col(2);
+        // S(n, 2) / n! = (2^(n-1) - 1) / n!
+        assert_eq!(stirling[4], Rational::from((7, 24)));
+        let mut b1 = bell.clone();
+        b1[(0, 0)] = Rational::from(0);
+        assert_eq!(b1.log(), &y * &ex1);
+        assert_eq!(ex.derive_x(), ex.truncated(6, 7));
+        assert_eq!(ex.derive_y(), BiSeries::new(7, 6));
+        assert_eq!(bell.derive_y().integrate_y(), b1);
+        assert_eq!(bell.integrate_x().derive_x(), bell);
+        let short = BiSeries::<ModIntP32>::x(5, 5);
+        let e = (&short * &BiSeries::y(5, 5)).exp();
+        let diag: ShortSeq<ModIntP32> = e.row(3);
+        assert_eq!(diag[3], ModIntP32::from(Rational::from((1, 6))));
+    }
+}
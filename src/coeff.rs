@@ -1,5 +1,6 @@
 use std::ops::{Add, Sub, Mul, Neg, Div, AddAssign, SubAssign, MulAssign, DivAssign};
 use crate::mathtypes::{One, Zero};
+use crate::fastmul::{FastMul, mul_prefix_small_mod};
 use rug::{Complete, Rational};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
@@ -146,6 +147,12 @@ ring_from_str! { impl FromStr for ModIntP32 }
 
 forward_into_ref_field! { impl Field for ModIntP32 }
 
+impl FastMul for ModIntP32 {
+    fn mul_prefix(a: &[Self], b: &[Self], n: usize) -> Vec<Self> {
+        mul_prefix_small_mod(a, b, n, ModIntP32::MOD as u64, |c| c.x as u64, |x| ModIntP32 { x: x as u32 })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
 pub struct MersP31 {
     x: u32
@@ -232,7 +239,7 @@ impl Mul<MersP31> for MersP31 {
 
     #[inline]
     fn mul(self, other: MersP31) -> MersP31 {
-        let (r, k) = self.x.widening_mul(other.x);
+        let (r, k) = self.x.carrying_mul(other.x, 0);
         let res = (r & MersP31::MOD) + (r >> 31) + (k << 1);
         (MersP31 { x: res }).reduced()
     }
@@ -286,6 +293,12 @@ ring_from_str! { impl FromStr for MersP31 }
 
 forward_into_ref_field! { impl Field for MersP31 }
 
+impl FastMul for MersP31 {
+    fn mul_prefix(a: &[Self], b: &[Self], n: usize) -> Vec<Self> {
+        mul_prefix_small_mod(a, b, n, MersP31::MOD as u64, |c| c.x as u64, |x| MersP31 { x: x as u32 })
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Default, Hash)]
 pub struct MersP61 {
     x: u64
@@ -371,7 +384,7 @@ impl Mul<MersP61> for MersP61 {
 
     #[inline]
     fn mul(self, other: MersP61) -> MersP61 {
-        let (r, k) = self.x.widening_mul(other.x);
+        let (r, k) = self.x.carrying_mul(other.x, 0);
         let res = (r & MersP61::MOD) + (r >> 61) + (k << 3);
         (MersP61 { x: res }).reduced()
     }
@@ -434,6 +447,9 @@ ring_from_str! { impl FromStr for MersP61 }
 
 forward_into_ref_field! { impl Field for MersP61 }
 
+// Values are too wide for the NTT primes, so this keeps the Karatsuba default
+impl FastMul for MersP61 { }
+
 #[cfg(test)]
 mod tests {
     use crate::coeff::{MersP31, MersP61};
@@ -0,0 +1,54 @@
+use crate::lexer::ParseError;
+
+#[derive(Debug)]
+pub enum PerqError {
+    // Lexing or parsing failed, the span points into the input
+    Parse(ParseError),
+    DivisionByZero,
+    // Operation not defined for the given value, like compose with a nonzero constant term
+    Domain(String),
+    // Wrong kind or number of arguments
+    InvalidArgument(String),
+    UnknownIdentifier(String),
+    UnknownFunction(String),
+    MissingANumber(u32),
+    // Malformed line in a sequence database, lines are numbered from 1
+    Database { line: usize, msg: String },
+    Io(std::io::Error),
+}
+
+impl std::fmt::Display for PerqError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            PerqError::Parse(err) => write!(f, "{}", err),
+            PerqError::DivisionByZero => write!(f, "Division by zero."),
+            PerqError::Domain(msg) | PerqError::InvalidArgument(msg) => write!(f, "{}", msg),
+            PerqError::UnknownIdentifier(name) => write!(f, "Identifier {} not found.", name),
+            PerqError::UnknownFunction(name) => write!(f, "Function {} not found.", name),
+            PerqError::MissingANumber(a_num) => write!(f, "Did not find A{:06} in database.", a_num),
+            PerqError::Database { line, msg } => write!(f, "Database line {}: {}", line, msg),
+            PerqError::Io(err) => write!(f, "{}", err),
+        }
+    }
+}
+
+impl std::error::Error for PerqError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PerqError::Io(err) => Some(err),
+            _ => None
+        }
+    }
+}
+
+impl From<ParseError> for PerqError {
+    fn from(err: ParseError) -> Self {
+        PerqError::Parse(err)
+    }
+}
+
+impl From<std::io::Error> for PerqError {
+    fn from(err: std::io::Error) -> Self {
+        PerqError::Io(err)
+    }
+}
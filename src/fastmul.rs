@@ -0,0 +1,224 @@
+use crate::Field;
+use std::cmp::min;
+
+// Below this many terms the schoolbook product is faster
+pub const FAST_MUL_THRESHOLD: usize = 32;
+const KARATSUBA_THRESHOLD: usize = 32;
+const NTT_THRESHOLD: usize = 64;
+
+// Coefficient types that can multiply long sequences faster than O(n^2).
+// The default is Karatsuba, types whose values fit in 31 bits override it with NTT.
+pub trait FastMul: Field + Copy {
+    // First n coefficients of the product of a and b, zero padded if the product is shorter
+    fn mul_prefix(a: &[Self], b: &[Self], n: usize) -> Vec<Self> {
+        karatsuba(a, b, n)
+    }
+}
+
+pub fn schoolbook<T: Field + Copy>(a: &[T], b: &[T], n: usize) -> Vec<T> {
+    let mut res = vec![T::zero(); n];
+    for (i, x) in a.iter().enumerate().take(n) {
+        for (j, y) in b.iter().enumerate().take(n - i) {
+            res[i + j] += *x * *y;
+        }
+    }
+    res
+}
+
+pub fn karatsuba<T: Field + Copy>(a: &[T], b: &[T], n: usize) -> Vec<T> {
+    // Terms past n can not contribute
+    let a = &a[..min(a.len(), n)];
+    let b = &b[..min(b.len(), n)];
+    if min(a.len(), b.len()) < KARATSUBA_THRESHOLD {
+        return schoolbook(a, b, n);
+    }
+    let len = a.len().max(b.len());
+    let mut pa = a.to_vec();
+    let mut pb = b.to_vec();
+    pa.resize(len, T::zero());
+    pb.resize(len, T::zero());
+    let mut res = vec![T::zero(); 2 * len - 1];
+    karatsuba_into(&pa, &pb, &mut res);
+    res.resize(n, T::zero());
+    res
+}
+
+// Adds the full product of a and b, both of the same length, into out
+fn karatsuba_into<T: Field + Copy>(a: &[T], b: &[T], out: &mut [T]) {
+    let len = a.len();
+    if len < KARATSUBA_THRESHOLD {
+        for (i, x) in a.iter().enumerate() {
+            for (j, y) in b.iter().enumerate() {
+                out[i + j] += *x * *y;
+            }
+        }
+        return;
+    }
+    let h = len / 2;
+    let (a0, a1) = a.split_at(h);
+    let (b0, b1) = b.split_at(h);
+    let mut z0 = vec![T::zero(); 2 * h - 1];
+    let mut z2 = vec![T::zero(); 2 * (len - h) - 1];
+    karatsuba_into(a0, b0, &mut z0);
+    karatsuba_into(a1, b1, &mut z2);
+    let mut sa = a1.to_vec();
+    let mut sb = b1.to_vec();
+    for i in 0..h {
+        sa[i] += a0[i];
+        sb[i] += b0[i];
+    }
+    let mut z1 = vec![T::zero(); 2 * (len - h) - 1];
+    karatsuba_into(&sa, &sb, &mut z1);
+    for (i, x) in z0.iter().enumerate() {
+        z1[i] -= *x;
+        out[i] += *x;
+    }
+    for (i, x) in z2.iter().enumerate() {
+        z1[i] -= *x;
+        out[i + 2 * h] += *x;
+    }
+    for (i, x) in z1.iter().enumerate() {
+        out[i + h] += *x;
+    }
+}
+
+// Primes of the form c*2^k+1 with primitive root 3, the smallest allows transforms of length 2^23
+const NTT_PRIMES: [u64; 3] = [998244353, 167772161, 469762049];
+const MAX_NTT_LEN: usize = 1 << 23;
+
+fn pow_mod(mut b: u64, mut e: u64, m: u64) -> u64 {
+    let mut res = 1;
+    b %= m;
+    while e > 0 {
+        if e & 1 == 1 { res = res * b % m; }
+        b = b * b % m;
+        e >>= 1;
+    }
+    res
+}
+
+// In place transform of a power of two length, inverse includes the division by the length
+fn ntt(a: &mut [u64], p: u64, invert: bool) {
+    let len = a.len();
+    let mut j = 0;
+    for i in 1..len {
+        let mut bit = len >> 1;
+        while j & bit != 0 {
+            j ^= bit;
+            bit >>= 1;
+        }
+        j |= bit;
+        if i < j { a.swap(i, j); }
+    }
+    let mut half = 1;
+    while half < len {
+        let root = pow_mod(3, (p - 1) / (2 * half as u64), p);
+        let root = if invert { pow_mod(root, p - 2, p) } else { root };
+        for block in a.chunks_mut(2 * half) {
+            let mut w = 1;
+            for k in 0..half {
+                let u = block[k];
+                let v = block[k + half] * w % p;
+                block[k] = if u + v >= p { u + v - p } else { u + v };
+                block[k + half] = if u >= v { u - v } else { u + p - v };
+                w = w * root % p;
+            }
+        }
+        half *= 2;
+    }
+    if invert {
+        let inv_len = pow_mod(len as u64, p - 2, p);
+        a.iter_mut().for_each(|x| *x = *x * inv_len % p);
+    }
+}
+
+fn ntt_prefix(a: &[u64], b: &[u64], n: usize, p: u64) -> Vec<u64> {
+    let len = (a.len() + b.len() - 1).next_power_of_two();
+    let mut fa: Vec<u64> = a.iter().map(|x| x % p).collect();
+    let mut fb: Vec<u64> = b.iter().map(|x| x % p).collect();
+    fa.resize(len, 0);
+    fb.resize(len, 0);
+    ntt(&mut fa, p, false);
+    ntt(&mut fb, p, false);
+    fa.iter_mut().zip(fb.iter()).for_each(|(x, y)| *x = *x * y % p);
+    ntt(&mut fa, p, true);
+    fa.truncate(n);
+    fa
+}
+
+// First n coefficients of the product of a and b modulo m, for values below 2^31.
+// The exact coefficients are rebuilt from three NTT primes with the CRT, their
+// product is about 2^86 so sums of up to 2^24 products of values below 2^31 fit.
+pub fn ntt_mul_mod(a: &[u64], b: &[u64], n: usize, m: u64) -> Vec<u64> {
+    let a = &a[..min(a.len(), n)];
+    let b = &b[..min(b.len(), n)];
+    if a.is_empty() || b.is_empty() {
+        return vec![0; n];
+    }
+    assert!(a.len() + b.len() - 1 <= MAX_NTT_LEN, "Product too long for NTT multiplication");
+    let [p1, p2, p3] = NTT_PRIMES;
+    let r1 = ntt_prefix(a, b, n, p1);
+    let r2 = ntt_prefix(a, b, n, p2);
+    let r3 = ntt_prefix(a, b, n, p3);
+    let inv_p1 = pow_mod(p1, p2 - 2, p2);
+    let inv_p12 = pow_mod(p1 * p2 % p3, p3 - 2, p3);
+    let mut res = vec![0; n];
+    for i in 0..r1.len() {
+        // Garner's algorithm, x = v1 + v2*p1 + v3*p1*p2
+        let v1 = r1[i];
+        let v2 = (r2[i] + p2 - v1 % p2) % p2 * inv_p1 % p2;
+        let low = (v1 + v2 % p3 * p1) % p3;
+        let v3 = (r3[i] + p3 - low) % p3 * inv_p12 % p3;
+        let x = v1 as u128 + v2 as u128 * p1 as u128 + v3 as u128 * (p1 * p2) as u128;
+        res[i] = (x % m as u128) as u64;
+    }
+    res
+}
+
+// NTT for long inputs, Karatsuba in between
+pub fn mul_prefix_small_mod<T: Field + Copy>(a: &[T], b: &[T], n: usize, m: u64, to_u64: fn(T) -> u64, from_u64: fn(u64) -> T) -> Vec<T> {
+    if min(min(a.len(), b.len()), n) < NTT_THRESHOLD {
+        return karatsuba(a, b, n);
+    }
+    let a: Vec<u64> = a.iter().map(|x| to_u64(*x)).collect();
+    let b: Vec<u64> = b.iter().map(|x| to_u64(*x)).collect();
+    ntt_mul_mod(&a, &b, n, m).into_iter().map(from_u64).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::fastmul::{schoolbook, karatsuba, ntt_mul_mod, FastMul};
+    use crate::{ModIntP32, MersP31, MersP61};
+
+    fn check<T: FastMul + std::fmt::Debug>() {
+        // Deterministic pseudo random values spread over the whole modulus
+        let mut state = 12345u64;
+        let mut next = || {
+            state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            T::from((state >> 33) as u32) * T::from((state >> 1) as u32)
+        };
+        for (la, lb, n) in [(1, 1, 1), (40, 40, 80), (100, 37, 90), (300, 300, 300), (513, 200, 1000), (64, 64, 10)] {
+            let a: Vec<T> = (0..la).map(|_| next()).collect();
+            let b: Vec<T> = (0..lb).map(|_| next()).collect();
+            let expected = schoolbook(&a, &b, n);
+            assert_eq!(karatsuba(&a, &b, n), expected);
+            assert_eq!(T::mul_prefix(&a, &b, n), expected);
+        }
+    }
+
+    #[test]
+    fn test_fast_mul() {
+        check::<ModIntP32>();
+        check::<MersP31>();
+        check::<MersP61>();
+    }
+
+    #[test]
+    fn test_ntt_extremes() {
+        let m = (1u64 << 31) - 1;
+        let a = vec![m - 1; 2000];
+        let res = ntt_mul_mod(&a, &a, 2000, m);
+        // Coefficient i is (i+1)(m-1)^2 = i+1 mod m
+        assert!(res.iter().enumerate().all(|(i, x)| *x == i as u64 + 1));
+    }
+}
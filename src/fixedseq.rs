@@ -1,40 +1,41 @@
 use std::ops::{Add, Sub, Neg, Mul, Div, AddAssign, SubAssign, MulAssign, DivAssign, Index, IndexMut};
 use std::cmp::min;
-use crate::{Field, PowerSeries};
+use crate::{FastMul, PowerSeries};
+use crate::fastmul::FAST_MUL_THRESHOLD;
 use crate::mathtypes::{Zero, One};
 use rug::Rational;
 use std::iter::zip;
 
 #[derive(Debug, Clone, Copy, Hash, PartialEq, Eq, PartialOrd, Ord)]
-pub struct FixedSeq<T: Field + Copy, const N: usize> {
+pub struct FixedSeq<T: FastMul, const N: usize> {
     pub seq: [T; N],
-    pub cnt: u8
+    pub cnt: u16
 }
 
 pub type ShortSeq<T> = FixedSeq<T, 16>;
 
-impl<T: Field + Copy, const N: usize> From<u32> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> From<u32> for FixedSeq<T, N> {
     #[inline]
     fn from(x: u32) -> Self {
         Self::promote(T::from(x))
     }
 }
 
-impl<T: Field + Copy, const N: usize> From<Rational> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> From<Rational> for FixedSeq<T, N> {
     #[inline]
     fn from(x: Rational) -> Self {
         Self::promote(T::from(x))
     }
 }
 
-impl<T: Field + Copy, const N: usize> One for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> One for FixedSeq<T, N> {
     #[inline]
     fn one() -> Self {
         let mut seq = [T::zero(); N];
         seq[0] = T::one();
         Self {
             seq,
-            cnt: N as u8
+            cnt: Self::FULL
         }
     }
     #[inline]
@@ -49,12 +50,12 @@ impl<T: Field + Copy, const N: usize> One for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> Zero for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> Zero for FixedSeq<T, N> {
     #[inline]
     fn zero() -> Self {
         Self {
             seq: [T::zero(); N],
-            cnt: N as u8
+            cnt: Self::FULL
         }
     }
     #[inline]
@@ -63,7 +64,7 @@ impl<T: Field + Copy, const N: usize> Zero for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> AddAssign<FixedSeq<T, N>> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> AddAssign<FixedSeq<T, N>> for FixedSeq<T, N> {
     #[inline]
     fn add_assign(&mut self, other: FixedSeq<T, N>) {
         zip(self.seq.iter_mut(), other.seq.iter()).for_each(|(x, y)| *x += y);
@@ -71,7 +72,7 @@ impl<T: Field + Copy, const N: usize> AddAssign<FixedSeq<T, N>> for FixedSeq<T,
     }
 }
 
-impl<T: Field + Copy, const N: usize> Add<FixedSeq<T, N>> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> Add<FixedSeq<T, N>> for FixedSeq<T, N> {
     type Output = FixedSeq<T, N>;
 
     #[inline]
@@ -82,7 +83,7 @@ impl<T: Field + Copy, const N: usize> Add<FixedSeq<T, N>> for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> Neg for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> Neg for FixedSeq<T, N> {
     type Output = FixedSeq<T, N>;
 
     #[inline]
@@ -98,7 +99,7 @@ impl<T: Field + Copy, const N: usize> Neg for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> SubAssign<FixedSeq<T, N>> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> SubAssign<FixedSeq<T, N>> for FixedSeq<T, N> {
     #[inline]
     fn sub_assign(&mut self, other: FixedSeq<T, N>) {
         zip(self.seq.iter_mut(), other.seq.iter()).for_each(|(x, y)| *x -= y);
@@ -106,7 +107,7 @@ impl<T: Field + Copy, const N: usize> SubAssign<FixedSeq<T, N>> for FixedSeq<T,
     }
 }
 
-impl<T: Field + Copy, const N: usize> Sub<FixedSeq<T, N>> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> Sub<FixedSeq<T, N>> for FixedSeq<T, N> {
     type Output = FixedSeq<T, N>;
 
     #[inline]
@@ -117,15 +118,19 @@ impl<T: Field + Copy, const N: usize> Sub<FixedSeq<T, N>> for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> Mul<FixedSeq<T, N>> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> Mul<FixedSeq<T, N>> for FixedSeq<T, N> {
     type Output = FixedSeq<T, N>;
 
     #[inline]
     fn mul(self, other: FixedSeq<T, N>) -> FixedSeq<T, N> {
         let mut seq: [T; N] = [T::zero(); N];
-        for i in 0..N {
-            for j in 0..N-i {
-                seq[i + j] += self.seq[i] * other.seq[j];
+        if N >= FAST_MUL_THRESHOLD {
+            seq.copy_from_slice(&T::mul_prefix(&self.seq, &other.seq, N));
+        } else {
+            for i in 0..N {
+                for j in 0..N-i {
+                    seq[i + j] += self.seq[i] * other.seq[j];
+                }
             }
         }
         FixedSeq::<T, N> {
@@ -135,14 +140,14 @@ impl<T: Field + Copy, const N: usize> Mul<FixedSeq<T, N>> for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> MulAssign<FixedSeq<T, N>> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> MulAssign<FixedSeq<T, N>> for FixedSeq<T, N> {
     #[inline]
     fn mul_assign(&mut self, other: FixedSeq<T, N>) {
         *self = *self * other;
     }
 }
 
-impl<T: Field + Copy, const N: usize> Div<FixedSeq<T, N>> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> Div<FixedSeq<T, N>> for FixedSeq<T, N> {
     type Output = FixedSeq<T, N>;
 
     #[inline]
@@ -153,7 +158,7 @@ impl<T: Field + Copy, const N: usize> Div<FixedSeq<T, N>> for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> DivAssign<FixedSeq<T, N>> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> DivAssign<FixedSeq<T, N>> for FixedSeq<T, N> {
     #[inline]
     fn div_assign(&mut self, other: FixedSeq<T, N>) {
         for i in 0..N {
@@ -166,7 +171,7 @@ impl<T: Field + Copy, const N: usize> DivAssign<FixedSeq<T, N>> for FixedSeq<T,
     }
 }
 
-impl<T: Field + Copy, const N: usize> Index<usize> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> Index<usize> for FixedSeq<T, N> {
     type Output = T;
 
     #[inline]
@@ -175,14 +180,14 @@ impl<T: Field + Copy, const N: usize> Index<usize> for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> IndexMut<usize> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> IndexMut<usize> for FixedSeq<T, N> {
     #[inline]
     fn index_mut(&mut self, index: usize) -> &mut T {
         &mut self.seq[index]
     }
 }
 
-impl<T: Field + Copy, const N: usize> std::str::FromStr for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> std::str::FromStr for FixedSeq<T, N> {
     type Err = ();
 
     fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -201,7 +206,7 @@ impl<T: Field + Copy, const N: usize> std::str::FromStr for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> FromIterator<T> for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> FromIterator<T> for FixedSeq<T, N> {
     #[inline]
     fn from_iter<I: IntoIterator<Item=T>>(iter: I) -> Self {
         let mut seq: [T; N] = [T::zero(); N];
@@ -214,12 +219,17 @@ impl<T: Field + Copy, const N: usize> FromIterator<T> for FixedSeq<T, N> {
     }
 }
 
-impl<T: Field + Copy, const N: usize> PowerSeries for FixedSeq<T, N> {
+impl<T: FastMul, const N: usize> PowerSeries for FixedSeq<T, N> {
     type Coeff = T;
 
+    // Terms past cnt are unknown, so they are cleared the way a Series is padded with zeros
     #[inline]
-    fn expand_to(&mut self, _l: usize) {
-
+    fn expand_to(&mut self, l: usize) {
+        let l = min(l, N);
+        if l > self.cnt as usize {
+            self.seq[self.cnt as usize..l].fill(T::zero());
+            self.cnt = l as u16;
+        }
     }
 
     #[inline]
@@ -229,7 +239,7 @@ impl<T: Field + Copy, const N: usize> PowerSeries for FixedSeq<T, N> {
 
     #[inline]
     fn limit_accuracy(&mut self, l: usize) {
-        self.cnt = min(l as u8, self.cnt);
+        self.cnt = min(l, self.cnt as usize) as u16;
     }
 
     #[inline]
@@ -252,14 +262,20 @@ impl<T: Field + Copy, const N: usize> PowerSeries for FixedSeq<T, N> {
         }
         Self {
             seq,
-            cnt: min(N as u8, self.cnt + 1)
+            cnt: min(Self::FULL, self.cnt + 1)
         }
     }
 }
 
-forward_into_ref_field! { impl Field for FixedSeq<T, N> where T: Field + std::marker::Copy, const N: usize }
+forward_into_ref_field! { impl Field for FixedSeq<T, N> where T: FastMul, const N: usize }
+
+impl<T: FastMul, const N: usize> FixedSeq<T, N> {
+    // The count of a fully known sequence, N must fit in cnt
+    const FULL: u16 = {
+        assert!(N <= u16::MAX as usize, "FixedSeq length does not fit in u16");
+        N as u16
+    };
 
-impl<T: Field + Copy, const N: usize> FixedSeq<T, N> {
     pub fn from_series(s: &crate::Series) -> Self {
         let acc = min(s.accuracy(), N);
         let mut res = FixedSeq::<T, N>::zero();
@@ -288,6 +304,21 @@ mod tests {
         assert_eq!(catalan, sq);
     }
 
+    #[test]
+    fn long_mul_test() {
+        use crate::fixedseq::FixedSeq;
+        use crate::{MersP31, MersP61};
+        // 1/(1-x)^2 has coefficients n+1, the products go through NTT and Karatsuba
+        let geom: FixedSeq::<MersP31, 300> = repeat(MersP31::from(1)).collect();
+        let sq = geom * geom;
+        assert!((0..300).all(|i| sq[i] == MersP31::from(i as u32 + 1)));
+        assert_eq!(sq / geom, geom);
+        let geom: FixedSeq::<MersP61, 100> = repeat(MersP61::from(1u32)).collect();
+        let sq = geom * geom;
+        assert!((0..100).all(|i| sq[i] == MersP61::from(i as u32 + 1)));
+        assert_eq!(sq.accuracy(), 100);
+    }
+
     #[test]
     fn div_test() {
         let geom: ShortSeq::<ModIntP32> = repeat(ModIntP32::from(1)).collect();
@@ -0,0 +1,194 @@
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EntryKind {
+    Transform,
+    Convolution,
+    Construction,
+    Hops,
+    KnownSeries,
+    Arithmetic,
+    Operator,
+    Command,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct HelpEntry {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub kind: EntryKind,
+    pub arity: usize,
+    pub condition: Option<&'static str>,
+    pub description: &'static str,
+}
+
+const fn entry(name: &'static str, usage: &'static str, kind: EntryKind, arity: usize, condition: Option<&'static str>, description: &'static str) -> HelpEntry {
+    HelpEntry { name, usage, kind, arity, condition, description }
+}
+
+use EntryKind::*;
+
+pub const HELP_ENTRIES: &[HelpEntry] = &[
+    entry("point", "point(a)", Transform, 1, None, "Multiply a(n) by n, x*d/dx on the generating function."),
+    entry("sqrt", "sqrt(a)", Transform, 1, Some("requires a square leading coefficient"), "Square root of the generating function, a Puiseux series if a(0)=0."),
+    entry("derive", "derive(a)", Transform, 1, None, "Derivative of the generating function."),
+    entry("integrate", "integrate(a)", Transform, 1, None, "Integral of the generating function with zero constant term."),
+    entry("log_derive", "log_derive(a)", Transform, 1, Some("requires a(0)!=0"), "Logarithmic derivative a'/a."),
+    entry("exp_integ", "exp_integ(a)", Transform, 1, None, "Exponential of the integral of the generating function."),
+    entry("inverse", "inverse(a)", Transform, 1, Some("requires a(0)=0 and a(1)!=0"), "Compositional inverse (series reversion)."),
+    entry("exp", "exp(a)", Transform, 1, Some("requires a(0)=0"), "Exponential of the generating function."),
+    entry("log", "log(a)", Transform, 1, Some("requires a(0)=1"), "Logarithm of the generating function."),
+    entry("delta", "delta(a)", Transform, 1, None, "First differences a(n+1)-a(n)."),
+    entry("partial_sums", "partial_sums(a)", Transform, 1, None, "Partial sums a(0)+...+a(n)."),
+    entry("partial_products", "partial_products(a)", Transform, 1, None, "Partial products a(0)*...*a(n)."),
+    entry("t019", "t019(a)", Transform, 1, None, "Second differences a(n+2)-2a(n+1)+a(n)."),
+    entry("laplace", "laplace(a)", Transform, 1, None, "Multiply a(n) by n!, turning an EGF into an OGF."),
+    entry("laplace_inv", "laplace_inv(a)", Transform, 1, None, "Divide a(n) by n!, turning an OGF into an EGF."),
+    entry("bous", "bous(a)", Transform, 1, None, "Boustrophedon transform."),
+    entry("bous_inv", "bous_inv(a)", Transform, 1, None, "Inverse boustrophedon transform."),
+    entry("mobius", "mobius(a)", Transform, 1, None, "Mobius transform, sum of mu(n/d)a(d) over divisors d of n."),
+    entry("mobius_inv", "mobius_inv(a)", Transform, 1, None, "Inverse Mobius transform, sum of a(d) over divisors d of n."),
+    entry("stirling", "stirling(a)", Transform, 1, None, "Stirling transform, sum of S2(n,k)a(k)."),
+    entry("stirling_inv", "stirling_inv(a)", Transform, 1, None, "Inverse Stirling transform, sum of s(n,k)a(k)."),
+    entry("euler", "euler(a)", Transform, 1, None, "Euler transform, counts multisets of a-structures."),
+    entry("euler_inv", "euler_inv(a)", Transform, 1, None, "Inverse Euler transform."),
+    entry("lah", "lah(a)", Transform, 1, None, "Lah transform, sum of L(n,k)a(k)."),
+    entry("lah_inv", "lah_inv(a)", Transform, 1, None, "Inverse Lah transform."),
+    entry("powerset", "powerset(a)", Transform, 1, None, "Weigh transform, counts sets of distinct a-structures."),
+    entry("lshift", "lshift(a)", Transform, 1, None, "Drop a(0) and shift left, (A(x)-a(0))/x."),
+    entry("rshift", "rshift(a)", Transform, 1, None, "Shift right, x*A(x)."),
+    entry("hadamard", "hadamard(a, b)", Convolution, 2, None, "Termwise product a(n)*b(n), same as a .* b."),
+    entry("exp_mul", "exp_mul(a, b)", Convolution, 2, None, "Binomial convolution, product of the EGFs."),
+    entry("dirichlet", "dirichlet(a, b)", Convolution, 2, None, "Dirichlet convolution, sum of a(d)b(n/d) over divisors d of n."),
+    entry("seq", "seq(a)", Construction, 1, Some("requires a(0)=0"), "Sequences of a-structures, 1/(1-A), labelled or not."),
+    entry("seq_k", "seq_k(a, k)", Construction, 2, Some("requires a(0)=0"), "Sequences of exactly k a-structures, A^k."),
+    entry("mset", "mset(a)", Construction, 1, Some("requires a(0)=0"), "Unlabelled multisets, exp(A(x)+A(x^2)/2+A(x^3)/3+...)."),
+    entry("mset_k", "mset_k(a, k)", Construction, 2, Some("requires a(0)=0"), "Unlabelled multisets of exactly k a-structures."),
+    entry("pset", "pset(a)", Construction, 1, Some("requires a(0)=0"), "Unlabelled sets of distinct a-structures, exp(A(x)-A(x^2)/2+A(x^3)/3-...)."),
+    entry("pset_k", "pset_k(a, k)", Construction, 2, Some("requires a(0)=0"), "Unlabelled sets of exactly k distinct a-structures."),
+    entry("cyc", "cyc(a)", Construction, 1, Some("requires a(0)=0"), "Unlabelled cycles, sum of phi(k)/k*log(1/(1-A(x^k)))."),
+    entry("cyc_k", "cyc_k(a, k)", Construction, 2, Some("requires a(0)=0 and k>0"), "Unlabelled cycles of exactly k a-structures."),
+    entry("set", "set(a)", Construction, 1, Some("requires a(0)=0"), "Labelled sets, exp(A) for an EGF."),
+    entry("set_k", "set_k(a, k)", Construction, 2, Some("requires a(0)=0"), "Labelled sets of exactly k a-structures, A^k/k!."),
+    entry("lcyc", "lcyc(a)", Construction, 1, Some("requires a(0)=0"), "Labelled cycles, log(1/(1-A)) for an EGF."),
+    entry("lcyc_k", "lcyc_k(a, k)", Construction, 2, Some("requires a(0)=0 and k>0"), "Labelled cycles of exactly k a-structures, A^k/k."),
+    entry("BINOMIAL", "BINOMIAL(a, m)", Hops, 2, None, "Sum of C(n,k)m^(n-k)a(k), m defaults to 1."),
+    entry("BINOMIALi", "BINOMIALi(a, m)", Hops, 2, None, "Inverse of BINOMIAL(a, m), m defaults to 1."),
+    entry("CATALAN", "CATALAN(a)", Hops, 1, None, "A(x*C(x)) with C the Catalan numbers."),
+    entry("CATALANi", "CATALANi(a)", Hops, 1, None, "A(x-x^2), inverse of CATALAN."),
+    entry("M2", "M2(a)", Hops, 1, None, "a(0), 2a(1), 2a(2), ..."),
+    entry("M2i", "M2i(a)", Hops, 1, None, "a(0), a(1)/2, a(2)/2, ..."),
+    entry("NEGATE", "NEGATE(a)", Hops, 1, None, "a(0), -a(1), -a(2), ..."),
+    entry("PSUM", "PSUM(a)", Hops, 1, None, "Partial sums, same as partial_sums."),
+    entry("PSUMSIGN", "PSUMSIGN(a)", Hops, 1, None, "Alternating partial sums, A/(1+x)."),
+    entry("PRODS", "PRODS(a)", Hops, 1, None, "Partial products, same as partial_products."),
+    entry("DIFF", "DIFF(a)", Hops, 1, None, "First differences, same as delta."),
+    entry("T019", "T019(a)", Hops, 1, None, "Second differences, same as t019."),
+    entry("LEFT", "LEFT(a)", Hops, 1, None, "Drop a(0), same as lshift."),
+    entry("RIGHT", "RIGHT(a)", Hops, 1, None, "1 + x*A(x)."),
+    entry("AERATE", "AERATE(a, k)", Hops, 2, Some("k non-negative integer"), "A(x^(k+1)), k zeros between terms, k defaults to 1."),
+    entry("AERATE1", "AERATE1(a)", Hops, 1, None, "A(x^2)."),
+    entry("AERATE2", "AERATE2(a)", Hops, 1, None, "A(x^3)."),
+    entry("BISECT0", "BISECT0(a)", Hops, 1, None, "a(0), a(2), a(4), ..."),
+    entry("BISECT1", "BISECT1(a)", Hops, 1, None, "a(1), a(3), a(5), ..."),
+    entry("TRISECT0", "TRISECT0(a)", Hops, 1, None, "a(0), a(3), a(6), ..."),
+    entry("TRISECT1", "TRISECT1(a)", Hops, 1, None, "a(1), a(4), a(7), ..."),
+    entry("TRISECT2", "TRISECT2(a)", Hops, 1, None, "a(2), a(5), a(8), ..."),
+    entry("POINT", "POINT(a)", Hops, 1, None, "n*a(n), same as point."),
+    entry("WEIGHT", "WEIGHT(a)", Hops, 1, None, "Weigh transform, same as powerset."),
+    entry("EULER", "EULER(a)", Hops, 1, None, "Euler transform, same as euler."),
+    entry("EULERi", "EULERi(a)", Hops, 1, None, "Inverse Euler transform."),
+    entry("BOUS", "BOUS(a)", Hops, 1, None, "Boustrophedon transform."),
+    entry("BOUSi", "BOUSi(a)", Hops, 1, None, "Inverse boustrophedon transform."),
+    entry("MOBIUS", "MOBIUS(a)", Hops, 1, None, "Mobius transform."),
+    entry("MOBIUSi", "MOBIUSi(a)", Hops, 1, None, "Inverse Mobius transform."),
+    entry("STIRLING", "STIRLING(a)", Hops, 1, None, "Stirling transform."),
+    entry("STIRLINGi", "STIRLINGi(a)", Hops, 1, None, "Inverse Stirling transform."),
+    entry("LAH", "LAH(a)", Hops, 1, None, "Lah transform."),
+    entry("LAHi", "LAHi(a)", Hops, 1, None, "Inverse Lah transform."),
+    entry("REVERT", "REVERT(a)", Hops, 1, Some("requires a(0)!=0"), "x*B(x) is the reversion of x*A(x)."),
+    entry("REVEGF", "REVEGF(a)", Hops, 1, Some("requires a(0)!=0"), "REVERT for exponential generating functions."),
+    entry("CONV", "CONV(a)", Hops, 1, None, "A(x)^2."),
+    entry("EXPCONV", "EXPCONV(a)", Hops, 1, None, "Sum of C(n,k)a(k)a(n-k), the square of the EGF."),
+    entry("EXP", "EXP(a)", Hops, 1, Some("requires a(0)=0"), "exp(A), same as exp."),
+    entry("LOG", "LOG(a)", Hops, 1, Some("requires a(0)=1"), "log(A), same as log."),
+    entry("x", "x", KnownSeries, 0, None, "The formal variable, reserved, so 1/(1-x-x^2) is a series."),
+    entry("sin", "sin", KnownSeries, 0, None, "Taylor series of sin(x)."),
+    entry("cos", "cos", KnownSeries, 0, None, "Taylor series of cos(x)."),
+    entry("tan", "tan", KnownSeries, 0, None, "Taylor series of tan(x)."),
+    entry("expx", "expx", KnownSeries, 0, None, "Taylor series of exp(x)."),
+    entry("log1px", "log1px", KnownSeries, 0, None, "Taylor series of log(1+x)."),
+    entry("factorial", "factorial(n)", Arithmetic, 1, Some("n non-negative integer"), "n!, also written with postfix !."),
+    entry("binomial", "binomial(n, k)", Arithmetic, 2, Some("k integer"), "Binomial coefficient n(n-1)...(n-k+1)/k!, n may be any rational."),
+    entry("gcd", "gcd(a, b)", Arithmetic, 2, Some("a, b integers"), "Greatest common divisor, never negative."),
+    entry("lcm", "lcm(a, b)", Arithmetic, 2, Some("a, b integers"), "Least common multiple, never negative."),
+    entry("floor", "floor(c)", Arithmetic, 1, None, "Largest integer at most c."),
+    entry("ceil", "ceil(c)", Arithmetic, 1, None, "Smallest integer at least c."),
+    entry("numer", "numer(c)", Arithmetic, 1, None, "Numerator of c in lowest terms, carries the sign."),
+    entry("denom", "denom(c)", Arithmetic, 1, None, "Denominator of c in lowest terms, always positive."),
+    entry("mod", "mod(a, m)", Arithmetic, 2, Some("m!=0"), "Remainder a - m*floor(a/m), has the sign of m."),
+    entry("add", "a + b", Operator, 2, None, "Sum of series or constants."),
+    entry("sub", "a - b", Operator, 2, None, "Difference of series or constants, also unary negation."),
+    entry("mul", "a * b", Operator, 2, None, "Product of series or constants."),
+    entry("div", "a / b", Operator, 2, Some("requires b!=0"), "Quotient of series or constants, a Laurent series if b(0)=0."),
+    entry("pow", "a ^ p", Operator, 2, Some("p constant, rational p requires a perfect power as leading coefficient"), "Power of a series or an exact power of a constant, a Laurent or Puiseux series if a(0)=0."),
+    entry("compose", "a @ b", Operator, 2, Some("requires b(0)=0"), "Composition A(B(x))."),
+    entry("point_mul", "a .* b", Operator, 2, None, "Termwise product a(n)*b(n)."),
+    entry("point_div", "a ./ b", Operator, 2, Some("requires b(n)!=0 for all n"), "Termwise quotient a(n)/b(n)."),
+    entry("let", "let v := expr", Command, 2, None, "Bind the value of an expression to a variable."),
+    entry("letrec", "letrec f := expr", Command, 2, Some("coefficient n of expr may only depend on lower coefficients of f"), "Solve a functional equation for f coefficient by coefficient."),
+    entry("equation", "f = expr", Command, 2, Some("as for letrec"), "HOPS equation, solved like letrec f := expr. HOPS series {1, 2} are read as [1, 2]."),
+    entry("def", "def f(a, b) := expr", Command, 2, None, "Define a function, the parameters are only visible inside expr."),
+    entry("help", "help(name)", Command, 1, None, "List everything available, or describe a single name."),
+    entry("search", "search(a)", Command, 1, None, "Search the database for transforms of a, the series of result i is r[i]."),
+    entry("score", "score(r, i)", Command, 2, None, "Score of the i-th search result."),
+    entry("anumber", "anumber(r, i)", Command, 2, None, "A-number matched by the i-th search result."),
+    entry("formula", "formula(r, i)", Command, 2, None, "Formula of the i-th search result."),
+    entry("save", "save(\"file\")", Command, 1, None, "Write all variables, functions and the precision to a file that load reads back."),
+    entry("load", "load(\"file\")", Command, 1, None, "Run a file written by save, or any other script, in the current session."),
+    entry("coeff", "coeff(s, n)", Command, 2, Some("n below the precision of s"), "Coefficient n of s, also written s[n]. Slices s[a..b] keep coefficients a up to b-1."),
+    entry("set_precision", "set_precision(n)", Command, 1, Some("n positive integer"), "Set the number of terms computed for known series and recursions."),
+    entry("set_echo", "set_echo(b)", Command, 1, None, "Print name = value after assignments when b is nonzero, print nothing when zero."),
+    entry("set_format", "set_format(\"f\")", Command, 1, Some("f one of ogf, egf, list, latex, mathml"), "Print series as x-polynomials, as sums of a(n)x^n/n!, as term lists, or as LaTeX or MathML."),
+    entry("set_order", "set_order(b)", Command, 1, None, "End printed series with + O(x^n) when b is nonzero, n the number of known terms."),
+    entry("precision", "precision(a)", Command, 1, None, "Number of known terms of a, or the default precision if omitted."),
+];
+
+pub fn lookup(name: &str) -> Option<&'static HelpEntry> {
+    HELP_ENTRIES.iter().find(|e| e.name == name)
+}
+
+impl std::fmt::Display for EntryKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Transform => write!(f, "Transforms"),
+            Convolution => write!(f, "Convolutions"),
+            Construction => write!(f, "Constructions"),
+            Hops => write!(f, "HOPS transforms"),
+            KnownSeries => write!(f, "Known series"),
+            Arithmetic => write!(f, "Constant functions"),
+            Operator => write!(f, "Operators"),
+            Command => write!(f, "Commands"),
+        }
+    }
+}
+
+impl std::fmt::Display for HelpEntry {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}: {}", self.usage, self.description)?;
+        if let Some(cond) = self.condition {
+            write!(f, " ({})", cond)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn overview() -> String {
+    let mut res = String::new();
+    for kind in [Transform, Convolution, Construction, Hops, KnownSeries, Arithmetic, Operator, Command] {
+        res += &format!("{}:\n", kind);
+        for e in HELP_ENTRIES.iter().filter(|e| e.kind == kind) {
+            res += &format!("  {}\n", e);
+        }
+    }
+    res.pop();
+    res
+}
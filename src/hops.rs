@@ -0,0 +1,106 @@
+use crate::{PowerSeries, Series, PerqError};
+use crate::mathtypes::One;
+use rug::Rational;
+
+// Transforms of the HOPS language (akc.is/hops) under their upper case names, so
+// formulas like BINOMIAL(LEFT(A000108)) can be pasted. The series comes first,
+// then any constant parameters.
+pub type HopsFn = fn(&Series, &[Rational]) -> Result<Series, PerqError>;
+
+fn param(p: &[Rational], default: i32) -> Rational {
+    p.first().cloned().unwrap_or(Rational::from(default))
+}
+
+fn count(p: &[Rational], default: usize) -> Result<usize, PerqError> {
+    let Some(c) = p.first() else {
+        return Ok(default);
+    };
+    match usize::try_from(c.numer()) {
+        Ok(n) if c.denom().is_one() => Ok(n),
+        _ => Err(PerqError::InvalidArgument("Parameter must be a non-negative integer.".to_string()))
+    }
+}
+
+// An unknown a(0) fails every condition on it
+fn first_is(a: &Series, cond: fn(&Rational) -> bool) -> bool {
+    a.seq.first().is_some_and(cond)
+}
+
+pub fn lookup(name: &str) -> Option<HopsFn> {
+    let f: HopsFn = match name {
+        "BINOMIAL" => |a, p| Ok(a.binomial_by(&param(p, 1))),
+        "BINOMIALi" => |a, p| Ok(a.binomial_by(&-param(p, 1))),
+        "CATALAN" => |a, _| Ok(a.catalan()),
+        "CATALANi" => |a, _| Ok(a.catalan_inv()),
+        "M2" => |a, _| Ok(a.m2()),
+        "M2i" => |a, _| Ok(a.m2_inv()),
+        "NEGATE" => |a, _| Ok(a.negate()),
+        "PSUM" => |a, _| Ok(a.partial_sums()),
+        "PSUMSIGN" => |a, _| Ok(a.partial_sums_signed()),
+        "PRODS" => |a, _| Ok(a.partial_products()),
+        "DIFF" => |a, _| Ok(a.delta()),
+        "T019" => |a, _| Ok(a.t019()),
+        "LEFT" => |a, _| Ok(a.lshift()),
+        "RIGHT" => |a, _| {
+            let mut res = a.rshift();
+            res[0] = Rational::one();
+            Ok(res)
+        },
+        // AERATE(a, k) puts k zeros between terms
+        "AERATE" => |a, p| {
+            let step = count(p, 1)?.checked_add(1)
+                .ok_or_else(|| PerqError::InvalidArgument("Parameter too large.".to_string()))?;
+            // Any step past the known terms leaves only a(0)
+            Ok(a.aerate(step.min(a.accuracy().max(1))))
+        },
+        "AERATE1" => |a, _| Ok(a.aerate(2)),
+        "AERATE2" => |a, _| Ok(a.aerate(3)),
+        "BISECT0" => |a, _| Ok(a.section(2, 0)),
+        "BISECT1" => |a, _| Ok(a.section(2, 1)),
+        "TRISECT0" => |a, _| Ok(a.section(3, 0)),
+        "TRISECT1" => |a, _| Ok(a.section(3, 1)),
+        "TRISECT2" => |a, _| Ok(a.section(3, 2)),
+        "POINT" => |a, _| Ok(a.point()),
+        "WEIGHT" => |a, _| Ok(a.powerset()),
+        "EULER" => |a, _| Ok(a.euler()),
+        "EULERi" => |a, _| Ok(a.euler_inv()),
+        "BOUS" => |a, _| Ok(a.bous()),
+        "BOUSi" => |a, _| Ok(a.bous_inv()),
+        "MOBIUS" => |a, _| Ok(a.mobius()),
+        "MOBIUSi" => |a, _| Ok(a.mobius_inv()),
+        "STIRLING" => |a, _| Ok(a.stirling()),
+        "STIRLINGi" => |a, _| Ok(a.stirling_inv()),
+        "LAH" => |a, _| Ok(a.lah()),
+        "LAHi" => |a, _| Ok(a.lah_inv()),
+        "REVERT" => |a, _| {
+            if !first_is(a, |c| !c.is_zero()) {
+                return Err(PerqError::Domain("REVERT requires a(0)!=0.".to_string()));
+            }
+            Ok(a.revert())
+        },
+        "REVEGF" => |a, _| {
+            if !first_is(a, |c| !c.is_zero()) {
+                return Err(PerqError::Domain("REVEGF requires a(0)!=0.".to_string()));
+            }
+            Ok(a.revert_egf())
+        },
+        "CONV" => |a, _| Ok(a.clone() * a),
+        "EXPCONV" => |a, _| Ok(a.exp_mul(a)),
+        "EXP" => |a, _| {
+            if !first_is(a, |c| c.is_zero()) {
+                return Err(PerqError::Domain("EXP requires a(0)=0.".to_string()));
+            }
+            Ok(a.exp())
+        },
+        "LOG" => |a, _| {
+            if !first_is(a, |c| c.is_one()) {
+                return Err(PerqError::Domain("LOG requires a(0)=1.".to_string()));
+            }
+            let mut t = a.clone();
+            t[0] = Rational::new();
+            Ok(t.log())
+        },
+        _ => { return None; }
+    };
+    Some(f)
+}
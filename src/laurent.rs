@@ -0,0 +1,318 @@
+use crate::{arith, PowerSeries, Series, SeriesFormat, PerqError};
+use crate::mathtypes::{Zero, One};
+use rug::Rational;
+use std::ops::Neg;
+use std::iter::{once, repeat_n};
+
+// The series x^val s(x^(1/den)), Laurent series have den 1 and Puiseux series
+// a larger den. Leading zeros are moved into val, so s(0) is nonzero unless s is.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Laurent {
+    pub val: Rational,
+    pub den: u32,
+    pub series: Series
+}
+
+fn gcd(a: u32, b: u32) -> u32 {
+    if b == 0 { a } else { gcd(b, a % b) }
+}
+
+fn lcm(a: u32, b: u32) -> Option<u32> {
+    (a / gcd(a, b)).checked_mul(b)
+}
+
+// Terms in x^(1/den) an operation may spell out beyond those of its operands,
+// far apart exponents need many of them
+const MAX_TERMS: usize = 1 << 12;
+
+fn too_far_apart() -> PerqError {
+    PerqError::Domain("Exponents too far apart to combine.".to_string())
+}
+
+impl From<Series> for Laurent {
+    fn from(series: Series) -> Self {
+        Laurent { val: Rational::new(), den: 1, series }.normalized()
+    }
+}
+
+impl Neg for &Laurent {
+    type Output = Laurent;
+
+    fn neg(self) -> Laurent {
+        Laurent { val: self.val.clone(), den: self.den, series: -self.series.clone() }
+    }
+}
+
+impl Laurent {
+    // Strips leading zeros and uses the largest step between the nonzero terms that
+    // also divides the number of known terms, so the order stays the same
+    fn normalized(mut self) -> Self {
+        let zeros = self.series.seq.iter().take_while(|c| c.is_zero()).count();
+        if zeros == self.series.accuracy() {
+            return self;
+        }
+        self.series.seq.drain(..zeros);
+        self.val += Rational::from((zeros as u32, self.den));
+        let step = self.series.seq.iter().enumerate()
+            .filter(|(_, c)| !c.is_zero())
+            .fold(gcd(self.den, self.series.accuracy() as u32), |g, (i, _)| gcd(g, i as u32));
+        if step > 1 {
+            self.series = self.series.seq.iter().step_by(step as usize).cloned().collect();
+            self.den /= step;
+        }
+        self
+    }
+
+    // The series in x^(1/den) for a multiple den of self.den, known terms between
+    // the old ones are zero
+    fn ramified(&self, den: u32) -> Series {
+        let k = (den / self.den) as usize;
+        self.series.seq.iter().flat_map(|c| once(c.clone()).chain(repeat_n(Rational::new(), k - 1))).collect()
+    }
+
+    // Fails if ramified(den) after n leading zeros has more than limit terms
+    fn check_ramified(&self, den: u32, n: usize, limit: usize) -> Result<(), PerqError> {
+        let len = self.series.accuracy().checked_mul((den / self.den) as usize).and_then(|l| l.checked_add(n));
+        len.filter(|l| *l <= limit).map(|_| ()).ok_or_else(too_far_apart)
+    }
+
+    fn term_limit(a: &Laurent, b: &Laurent) -> usize {
+        MAX_TERMS.max(a.series.accuracy() + b.series.accuracy())
+    }
+
+    // Both series over the same x^val and x^(1/den)
+    fn align(a: &Laurent, b: &Laurent) -> Result<(Rational, u32, Series, Series), PerqError> {
+        let den = lcm(a.den, b.den).ok_or_else(too_far_apart)?;
+        let diff = Rational::from(&a.val - &b.val) * den;
+        let den = diff.denom().to_u32().and_then(|d| den.checked_mul(d)).ok_or_else(too_far_apart)?;
+        let val = if a.val < b.val { a.val.clone() } else { b.val.clone() };
+        let pad = |l: &Laurent| -> Result<Series, PerqError> {
+            let n = (Rational::from(&l.val - &val) * den).numer().to_usize().ok_or_else(too_far_apart)?;
+            l.check_ramified(den, n, Laurent::term_limit(a, b))?;
+            Ok(repeat_n(Rational::new(), n).chain(l.ramified(den).seq).collect())
+        };
+        Ok((val.clone(), den, pad(a)?, pad(b)?))
+    }
+
+    pub fn checked_add(&self, other: &Laurent) -> Result<Self, PerqError> {
+        let (val, den, s, t) = Laurent::align(self, other)?;
+        Ok(Laurent { val, den, series: s + t }.normalized())
+    }
+
+    pub fn checked_sub(&self, other: &Laurent) -> Result<Self, PerqError> {
+        let (val, den, s, t) = Laurent::align(self, other)?;
+        Ok(Laurent { val, den, series: s - t }.normalized())
+    }
+
+    pub fn checked_mul(&self, other: &Laurent) -> Result<Self, PerqError> {
+        let den = lcm(self.den, other.den).ok_or_else(too_far_apart)?;
+        self.check_ramified(den, 0, Laurent::term_limit(self, other))?;
+        other.check_ramified(den, 0, Laurent::term_limit(self, other))?;
+        Ok(Laurent {
+            val: Rational::from(&self.val + &other.val),
+            den,
+            series: self.ramified(den) * other.ramified(den)
+        }.normalized())
+    }
+
+    // Exponent of the first unknown term
+    pub fn order(&self) -> Rational {
+        &self.val + Rational::from((self.series.accuracy() as u32, self.den))
+    }
+
+    // Constant c known up to the order of other
+    pub fn constant(c: Rational, other: &Laurent) -> Self {
+        let acc = other.order().ceil().numer().to_usize().unwrap_or(0);
+        let mut series = Series::promote(c);
+        series.set_accuracy(acc);
+        Laurent::from(series)
+    }
+
+    // A plain power series when there are no negative or fractional exponents
+    pub fn to_series(&self) -> Option<Series> {
+        if self.den != 1 || !self.val.denom().is_one() || self.val < 0 {
+            return None;
+        }
+        let shift = self.val.numer().to_usize()?;
+        Some(repeat_n(Rational::new(), shift).chain(self.series.seq.iter().cloned()).collect())
+    }
+
+    pub fn recip(&self) -> Result<Self, PerqError> {
+        if self.series.is_zero() {
+            return Err(PerqError::DivisionByZero);
+        }
+        Ok(Laurent { val: -self.val.clone(), den: self.den, series: self.series.recip() })
+    }
+
+    // c x^k (1 + ...) to the power p/q is c^(p/q) x^(kp/q) (1 + ...)^(p/q), so only the
+    // leading coefficient needs an exact root
+    pub fn ratpow(&self, p: i32, q: u32) -> Result<Self, PerqError> {
+        let exp = Rational::from((p, q));
+        let val = Rational::from(&self.val * &exp);
+        if self.series.is_zero() {
+            if p <= 0 {
+                return Err(PerqError::DivisionByZero);
+            }
+            return Ok(Laurent { val, den: self.den, series: self.series.clone() });
+        }
+        let lead = self.series[0].clone();
+        let root = arith::pow(&lead, &exp)?;
+        let unit: Series = self.series.seq.iter().map(|c| Rational::from(c / &lead)).collect();
+        let series = unit.ratpow(p, q).seq.into_iter().map(|c| c * &root).collect();
+        Ok(Laurent { val, den: self.den, series })
+    }
+
+    // Nonzero terms as exponent and coefficient
+    fn terms(&self) -> impl Iterator<Item=(Rational, &Rational)> {
+        self.series.seq.iter().enumerate()
+            .filter(|(_, c)| !c.is_zero())
+            .map(|(i, c)| (&self.val + Rational::from((i as u32, self.den)), c))
+    }
+
+    // Like Series::format with the unknown tail as + O(x^n) when order is set. There is
+    // no EGF of fractional or negative powers, so Egf is written as Ogf. The list starts
+    // with the first power and the step between the coefficients.
+    pub fn format(&self, fmt: SeriesFormat, order: bool) -> String {
+        match fmt {
+            SeriesFormat::Ogf | SeriesFormat::Egf => self.plain(order),
+            SeriesFormat::List => {
+                let start = if self.val.is_zero() { "1".to_string() } else { Laurent::power(&self.val) };
+                let step = Laurent::power(&Rational::from((1, self.den)));
+                let mut coeffs: Vec<String> = self.series.seq.iter().map(|c| c.to_string()).collect();
+                if order { coeffs.push("...".to_string()); }
+                format!("{} in steps of {}: {}", start, step, coeffs.join(", "))
+            },
+            SeriesFormat::Latex => {
+                let frac = |c: &Rational| if c.denom().is_one() { c.numer().to_string() } else { format!("\\frac{{{}}}{{{}}}", c.numer(), c.denom()) };
+                let power = |e: &Rational| {
+                    let sign = if *e < 0 { "-" } else { "" };
+                    let e = Rational::from(e.abs_ref());
+                    if sign.is_empty() && e == 1 { "x".to_string() } else { format!("x^{{{}{}}}", sign, frac(&e)) }
+                };
+                let mut res = String::new();
+                for (e, c) in self.terms() {
+                    res += match (*c < 0, res.is_empty()) { (true, true) => "-", (true, false) => " - ", (false, true) => "", (false, false) => " + " };
+                    let c = Rational::from(c.abs_ref());
+                    res += &match (e.is_zero(), c == 1) {
+                        (true, _) => frac(&c),
+                        (false, true) => power(&e),
+                        (false, false) => format!("{}{}", frac(&c), power(&e))
+                    };
+                }
+                if res.is_empty() { res = "0".to_string(); }
+                if order {
+                    let tail = format!("O({})", if self.order().is_zero() { "1".to_string() } else { power(&self.order()) });
+                    res = if res == "0" { tail } else { format!("{} + {}", res, tail) };
+                }
+                res
+            },
+            SeriesFormat::MathMl => {
+                let num = |c: &Rational| if c.denom().is_one() { format!("<mn>{}</mn>", c.numer()) } else { format!("<mfrac><mn>{}</mn><mn>{}</mn></mfrac>", c.numer(), c.denom()) };
+                let power = |e: &Rational| {
+                    let abs = Rational::from(e.abs_ref());
+                    let exp = if *e < 0 { format!("<mrow><mo>-</mo>{}</mrow>", num(&abs)) } else { num(&abs) };
+                    if *e == 1 { "<mi>x</mi>".to_string() } else { format!("<msup><mi>x</mi>{}</msup>", exp) }
+                };
+                let mut res = String::new();
+                for (e, c) in self.terms() {
+                    res += if *c < 0 { "<mo>-</mo>" } else if res.is_empty() { "" } else { "<mo>+</mo>" };
+                    let c = Rational::from(c.abs_ref());
+                    res += &match (e.is_zero(), c == 1) {
+                        (true, _) => num(&c),
+                        (false, true) => power(&e),
+                        (false, false) => format!("{}{}", num(&c), power(&e))
+                    };
+                }
+                if res.is_empty() { res = "<mn>0</mn>".to_string(); }
+                if order {
+                    if res != "<mn>0</mn>" { res += "<mo>+</mo>"; } else { res.clear(); }
+                    let tail = if self.order().is_zero() { "<mn>1</mn>".to_string() } else { power(&self.order()) };
+                    res += &format!("<mi>O</mi><mo>(</mo>{}<mo>)</mo>", tail);
+                }
+                format!("<math>{}</math>", res)
+            }
+        }
+    }
+
+    fn power(e: &Rational) -> String {
+        if e.is_zero() {
+            String::new()
+        } else if *e == 1 {
+            "x".to_string()
+        } else if e.denom().is_one() {
+            format!("x^{}", e)
+        } else {
+            format!("x^({})", e)
+        }
+    }
+
+    fn plain(&self, order: bool) -> String {
+        let mut res = String::new();
+        for (e, c) in self.terms() {
+            if *c > 0 && !res.is_empty() { res += "+"; }
+            if *c == -1 && !e.is_zero() {
+                res += "-";
+            } else if *c != 1 || e.is_zero() {
+                res += &c.to_string();
+            }
+            res += &Laurent::power(&e);
+        }
+        if res.is_empty() {
+            res = "0".to_string();
+        }
+        if order {
+            let tail = format!("O({})", Laurent::power(&self.order()));
+            res = if res == "0" { tail } else { format!("{} + {}", res, tail) };
+        }
+        res
+    }
+}
+
+impl std::fmt::Display for Laurent {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.format(SeriesFormat::Ogf, false))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Series, SeriesFormat, PerqError};
+    use crate::laurent::Laurent;
+    use rug::Rational;
+
+    #[test]
+    fn test_laurent() {
+        // 1/(x - x^2) = x^-1 + 1 + x + ...
+        let s: Series = "0,1,-1,0,0,0".parse().unwrap();
+        let inv = Laurent::from(s.clone()).recip().unwrap();
+        assert_eq!(inv.to_string(), "x^-1+1+x+x^2+x^3");
+        assert_eq!(inv.format(SeriesFormat::Ogf, true), "x^-1+1+x+x^2+x^3 + O(x^4)");
+        assert_eq!(inv.checked_mul(&Laurent::from(s)).unwrap().to_series(), Some("1,0,0,0,0".parse().unwrap()));
+        // sqrt(x + x^2) = x^(1/2) sqrt(1 + x)
+        let t: Series = "0,4,4,0,0,0".parse().unwrap();
+        let root = Laurent::from(t).ratpow(1, 2).unwrap();
+        assert_eq!(root.val, Rational::from((1, 2)));
+        assert_eq!(root.series, "2,1,-1/4,1/8,-5/64".parse().unwrap());
+        assert_eq!(root.to_string(), "2x^(1/2)+x^(3/2)-1/4x^(5/2)+1/8x^(7/2)-5/64x^(9/2)");
+        assert_eq!(root.to_series(), None);
+        assert_eq!(root.format(SeriesFormat::Latex, true), "2x^{\\frac{1}{2}} + x^{\\frac{3}{2}} - \\frac{1}{4}x^{\\frac{5}{2}} + \\frac{1}{8}x^{\\frac{7}{2}} - \\frac{5}{64}x^{\\frac{9}{2}} + O(x^{\\frac{11}{2}})");
+        assert_eq!(inv.format(SeriesFormat::List, false), "x^-1 in steps of x: 1, 1, 1, 1, 1");
+        assert_eq!(inv.format(SeriesFormat::MathMl, false), "<math><msup><mi>x</mi><mrow><mo>-</mo><mn>1</mn></mrow></msup><mo>+</mo><mn>1</mn><mo>+</mo><mi>x</mi><mo>+</mo><msup><mi>x</mi><mn>2</mn></msup><mo>+</mo><msup><mi>x</mi><mn>3</mn></msup></math>");
+        // Adding x^(1/2) to 1 needs steps of x^(1/2)
+        let one = Laurent::constant(Rational::from(1), &root);
+        let sum = one.checked_add(&root).unwrap();
+        assert_eq!(sum.den, 2);
+        assert_eq!(sum.to_string(), "1+2x^(1/2)+x^(3/2)-1/4x^(5/2)+1/8x^(7/2)-5/64x^(9/2)");
+        assert_eq!(sum.checked_sub(&root).unwrap().format(SeriesFormat::Ogf, true), "1 + O(x^(11/2))");
+        let sq = root.ratpow(2, 1).unwrap();
+        assert_eq!(sq.to_series(), Some("0,4,4,0,0,0".parse().unwrap()));
+        assert!(Laurent::from("0,2,0".parse::<Series>().unwrap()).ratpow(1, 2).is_err());
+        // x^(1/65537) + x^(1/65539) needs steps of x^(1/(65537*65539))
+        let x = Laurent::from("0,1,0,0".parse::<Series>().unwrap());
+        let (a, b) = (x.ratpow(1, 65537).unwrap(), x.ratpow(1, 65539).unwrap());
+        assert!(matches!(a.checked_add(&b), Err(PerqError::Domain(_))));
+        let (a, b) = (x.ratpow(1, 1000).unwrap(), x.ratpow(1, 1001).unwrap());
+        assert!(matches!(a.checked_mul(&b.recip().unwrap()), Ok(_)));
+        assert!(matches!(a.checked_sub(&b), Err(PerqError::Domain(_))));
+    }
+}
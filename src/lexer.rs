@@ -9,6 +9,7 @@ pub enum Operator {
     PointMul,
     PointDiv,
     DefineEqual,
+    Factorial,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -16,14 +17,105 @@ pub enum Token {
     Operator(Operator),
     Identifier(Vec<u8>),
     Literal(Vec<u8>),
+    Text(Vec<u8>),
     Comma,
     Semicolon,
+    Newline,
     OpenParen,
     CloseParen,
     OpenBracket,
     CloseBracket,
+    OpenBrace,
+    CloseBrace,
+    Equals,
+    DotDot,
     Let,
     LetRec,
+    Def,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Self { start, end }
+    }
+
+    pub fn join(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    pub msg: String,
+    pub span: Span,
+}
+
+impl ParseError {
+    pub fn new(msg: String, span: Span) -> Self {
+        Self { msg, span }
+    }
+
+    // Offending line of the source with the span underlined, followed by the message
+    pub fn render(&self, text: &[u8]) -> String {
+        let start = self.span.start.min(text.len());
+        let line_start = text[..start].iter().rposition(|c| *c == b'\n').map_or(0, |i| i + 1);
+        let line_end = text[start..].iter().position(|c| *c == b'\n').map_or(text.len(), |i| start + i);
+        let line_num = text[..line_start].iter().filter(|c| **c == b'\n').count() + 1;
+        let width = self.span.end.min(line_end).saturating_sub(start).max(1);
+        format!("{}\n{}{}\nLine {}, column {}: {}",
+            String::from_utf8_lossy(&text[line_start..line_end]),
+            " ".repeat(start - line_start), "^".repeat(width),
+            line_num, start - line_start + 1, self.msg)
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{} at character index {}", self.msg, self.span.start)
+    }
+}
+
+impl std::fmt::Display for Operator {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let (name, _) = OPERATORS.iter().find(|(_, op)| op == self).unwrap();
+        write!(f, "{}", name)
+    }
+}
+
+impl std::fmt::Display for Token {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Token::Operator(op) => write!(f, "{}", op),
+            Token::Identifier(x) | Token::Literal(x) => write!(f, "{}", String::from_utf8_lossy(x)),
+            Token::Text(x) => write!(f, "\"{}\"", String::from_utf8_lossy(x)),
+            Token::Comma => write!(f, ","),
+            Token::Semicolon => write!(f, ";"),
+            Token::Newline => write!(f, "newline"),
+            Token::OpenParen => write!(f, "("),
+            Token::CloseParen => write!(f, ")"),
+            Token::OpenBracket => write!(f, "["),
+            Token::CloseBracket => write!(f, "]"),
+            Token::OpenBrace => write!(f, "{{"),
+            Token::CloseBrace => write!(f, "}}"),
+            Token::Equals => write!(f, "="),
+            Token::DotDot => write!(f, ".."),
+            Token::Let => write!(f, "let"),
+            Token::LetRec => write!(f, "letrec"),
+            Token::Def => write!(f, "def"),
+        }
+    }
 }
 
 fn match_from_list<T, const N: usize>(text: &[u8], pos: &mut usize, list: [(&str, T); N]) -> Option<T> {
@@ -36,7 +128,7 @@ fn match_from_list<T, const N: usize>(text: &[u8], pos: &mut usize, list: [(&str
     None
 }
 
-const OPERATORS: [(&str, Operator); 9] = [
+const OPERATORS: [(&str, Operator); 10] = [
     ("+", Operator::Add),
     ("-", Operator::Sub),
     ("*", Operator::Mul),
@@ -46,63 +138,143 @@ const OPERATORS: [(&str, Operator); 9] = [
     (".*", Operator::PointMul),
     ("./", Operator::PointDiv),
     (":=", Operator::DefineEqual),
+    ("!", Operator::Factorial),
 ];
 
-const KEYWORDS: [(&str, Token); 2] = [
+const KEYWORDS: [(&str, Token); 3] = [
     ("let", Token::Let), 
     ("letrec", Token::LetRec),
+    ("def", Token::Def),
 ];
 
-pub fn parse_tokens(text: &[u8]) -> Result<Vec<Token>,String> {
+// A statement continues past the end of a line inside brackets or after an operator or comma,
+// except the postfix n!
+fn continues(res: &[SpannedToken], depth: i32) -> bool {
+    depth > 0 || matches!(res.last(), Some(SpannedToken { token: Token::Operator(op), .. }) if *op != Operator::Factorial)
+        || matches!(res.last(), Some(SpannedToken { token: Token::Comma | Token::Equals, .. }))
+}
+
+pub fn parse_tokens(text: &[u8]) -> Result<Vec<SpannedToken>, ParseError> {
     let mut res = vec![];
     let mut pos = 0;
+    let mut depth = 0;
     while pos < text.len() {
         if text[pos] != b'\n' && text[pos].is_ascii_whitespace() {
             pos += 1;
             continue;
         }
-        res.push(match text[pos] {
+        if text[pos] == b'#' {
+            pos = text[pos..].iter().position(|c| *c == b'\n').map_or(text.len(), |i| pos + i);
+            continue;
+        }
+        if text[pos..].starts_with(b"/*") {
+            let Some(len) = text[pos+2..].windows(2).position(|w| w == b"*/") else {
+                return Err(ParseError::new("Unterminated comment".to_string(), Span::new(pos, text.len())));
+            };
+            pos += len + 4;
+            continue;
+        }
+        if text[pos] == b'\n' && continues(&res, depth) {
+            pos += 1;
+            continue;
+        }
+        let start = pos;
+        let token = match text[pos] {
             b',' => { pos += 1; Token::Comma },
-            b';' | b'\n' => { pos += 1; Token::Semicolon },
-            b'(' => { pos += 1; Token::OpenParen },
-            b')' => { pos += 1; Token::CloseParen },
-            b'[' => { pos += 1; Token::OpenBracket },
-            b']' => { pos += 1; Token::CloseBracket },
+            b';' => { pos += 1; Token::Semicolon },
+            b'\n' => { pos += 1; Token::Newline },
+            b'(' => { pos += 1; depth += 1; Token::OpenParen },
+            // Text is used for file names, so there are no escapes
+            b'"' => {
+                let Some(len) = text[pos+1..].iter().position(|c| *c == b'"' || *c == b'\n') else {
+                    return Err(ParseError::new("Unterminated text".to_string(), Span::new(pos, text.len())));
+                };
+                if text[pos + 1 + len] == b'\n' {
+                    return Err(ParseError::new("Unterminated text".to_string(), Span::new(pos, pos + 1 + len)));
+                }
+                let contents = text[pos+1..pos+1+len].to_vec();
+                pos += len + 2;
+                Token::Text(contents)
+            },
+            b')' => { pos += 1; depth -= 1; Token::CloseParen },
+            b'[' => { pos += 1; depth += 1; Token::OpenBracket },
+            b']' => { pos += 1; depth -= 1; Token::CloseBracket },
+            // Braces and a lone = are HOPS syntax, {1, 2} is a series and f = expr an equation
+            b'{' => { pos += 1; depth += 1; Token::OpenBrace },
+            b'}' => { pos += 1; depth -= 1; Token::CloseBrace },
+            b'=' => { pos += 1; Token::Equals },
+            b'.' if text.get(pos + 1) == Some(&b'.') => { pos += 2; Token::DotDot },
             b'0' ..= b'9' => {
-                let mut end = pos + 1;
-                while end < text.len() && text[end].is_ascii_digit() { end += 1; }
+                let digit_at = |i: usize| i < text.len() && text[i].is_ascii_digit();
+                let skip_digits = |mut i: usize| { while digit_at(i) { i += 1; } i };
+                // Decimals and exponents are part of the literal, p/q stays a division
+                // of exact constants so x^2/2 keeps its usual meaning
+                let mut end = skip_digits(pos + 1);
+                if end < text.len() && text[end] == b'.' && digit_at(end + 1) {
+                    end = skip_digits(end + 1);
+                }
+                if end < text.len() && (text[end] == b'e' || text[end] == b'E') {
+                    if digit_at(end + 1) {
+                        end = skip_digits(end + 1);
+                    } else if end + 1 < text.len() && (text[end + 1] == b'+' || text[end + 1] == b'-') && digit_at(end + 2) {
+                        end = skip_digits(end + 2);
+                    }
+                }
                 let num = text[pos..end].to_vec();
                 pos = end;
                 Token::Literal(num)
             },
             b'a' ..= b'z' | b'A' ..= b'Z' | b'_' => {
-                match match_from_list(text, &mut pos, KEYWORDS) {
-                    Some(token) => token,
-                    None => {
-                        let mut end = pos + 1;
-                        while end < text.len() && (text[end].is_ascii_alphanumeric() || text[end] == b'_') { end += 1; }
-                        let name = text[pos..end].to_vec();
-                        pos = end;
-                        Token::Identifier(name)
-                    }
+                let mut end = pos + 1;
+                while end < text.len() && (text[end].is_ascii_alphanumeric() || text[end] == b'_') { end += 1; }
+                let name = text[pos..end].to_vec();
+                pos = end;
+                match KEYWORDS.iter().find(|(kw, _)| kw.as_bytes() == name) {
+                    Some((_, token)) => token.clone(),
+                    None => Token::Identifier(name)
                 }
             }
             _ => {
                 match match_from_list(text, &mut pos, OPERATORS) {
                     Some(op) => Token::Operator(op),
-                    None => return Err(format!("Unexpected token at character index {}.", pos))
+                    None => {
+                        let found = String::from_utf8_lossy(&text[pos..pos+1]).into_owned();
+                        return Err(ParseError::new(format!("Unexpected character {}", found), Span::new(pos, pos + 1)));
+                    }
                 }
             }
-        });
+        };
+        res.push(SpannedToken { token, span: Span::new(start, pos) });
     }
     Ok(res)
 }
 
+// True if more lines are needed to finish the last statement of text
+pub fn is_incomplete(text: &[u8]) -> bool {
+    match parse_tokens(text) {
+        Ok(res) => {
+            let depth = res.iter().map(|t| match t.token {
+                Token::OpenParen | Token::OpenBracket | Token::OpenBrace => 1,
+                Token::CloseParen | Token::CloseBracket | Token::CloseBrace => -1,
+                _ => 0
+            }).sum();
+            continues(&res, depth)
+        },
+        // Only an open block comment can still be finished by later lines
+        Err(err) => err.span.end == text.len() && text[err.span.start..].starts_with(b"/*")
+    }
+}
+
+#[cfg(test)]
+fn lex_plain(text: &str) -> Vec<Token> {
+    parse_tokens(text.as_bytes()).unwrap().into_iter().map(|t| t.token).collect()
+}
+
 #[test]
 fn lex_test_1() {
     use crate::lexer::Token::*;
     use crate::lexer::Operator::*;
-    let res = crate::lexer::parse_tokens("let x := [7,22, 3]".as_bytes()).unwrap();
+    let res = crate::lexer::lex_plain("let x := [7,22, 3]");
     let expected = [
         Let, 
         Identifier(vec![b'x']), 
@@ -117,3 +289,76 @@ fn lex_test_1() {
     ];
     assert!(res == expected);
 }
+
+#[test]
+fn lex_test_keywords() {
+    use crate::lexer::Token::*;
+    let res = crate::lexer::lex_plain("letrec f := letter");
+    assert_eq!(res[0], LetRec);
+    assert_eq!(res[1], Identifier(b"f".to_vec()));
+    assert_eq!(res[3], Identifier(b"letter".to_vec()));
+    assert_eq!(crate::lexer::lex_plain("def define"), [Def, Identifier(b"define".to_vec())]);
+}
+
+#[test]
+fn lex_test_spans() {
+    let res = crate::lexer::parse_tokens("ab + 12".as_bytes()).unwrap();
+    let spans: Vec<_> = res.iter().map(|t| (t.span.start, t.span.end)).collect();
+    assert_eq!(spans, [(0, 2), (3, 4), (5, 7)]);
+    let err = crate::lexer::parse_tokens("x := 1\ny $ 2".as_bytes()).unwrap_err();
+    assert_eq!(err.span, crate::lexer::Span::new(9, 10));
+    assert_eq!(err.render("x := 1\ny $ 2".as_bytes()), "y $ 2\n  ^\nLine 2, column 3: Unexpected character $");
+}
+
+#[test]
+fn lex_test_literals() {
+    use crate::lexer::Token::*;
+    use crate::lexer::Operator::*;
+    let lit = |s: &str| Literal(s.as_bytes().to_vec());
+    assert_eq!(lex_plain("1/2 3 / 4"), [lit("1"), Operator(Div), lit("2"), lit("3"), Operator(Div), lit("4")]);
+    assert_eq!(lex_plain("0.25 1e3 2.5E-4 7e+1"), [lit("0.25"), lit("1e3"), lit("2.5E-4"), lit("7e+1")]);
+    assert_eq!(lex_plain("[-1/6, 2./3]"), [OpenBracket, Operator(Sub), lit("1"), Operator(Div), lit("6"), Comma, lit("2"), Operator(PointDiv), lit("3"), CloseBracket]);
+    assert_eq!(lex_plain("2e x1/2"), [lit("2"), Identifier(b"e".to_vec()), Identifier(b"x1".to_vec()), Operator(Div), lit("2")]);
+}
+
+#[test]
+fn lex_test_ranges() {
+    use crate::lexer::Token::*;
+    use crate::lexer::Operator::*;
+    let lit = |s: &str| Literal(s.as_bytes().to_vec());
+    assert_eq!(lex_plain("s[1..5]"), [Identifier(b"s".to_vec()), OpenBracket, lit("1"), DotDot, lit("5"), CloseBracket]);
+    assert_eq!(lex_plain("a[2.5..] ./b"), [Identifier(b"a".to_vec()), OpenBracket, lit("2.5"), DotDot, CloseBracket, Operator(PointDiv), Identifier(b"b".to_vec())]);
+}
+
+#[test]
+fn lex_test_text() {
+    use crate::lexer::Token::*;
+    assert_eq!(lex_plain("save(\"my file.perq\")"), [Identifier(b"save".to_vec()), OpenParen, Text(b"my file.perq".to_vec()), CloseParen]);
+    assert_eq!(lex_plain("\"\""), [Text(vec![])]);
+    let err = crate::lexer::parse_tokens("load(\"a\nb\")".as_bytes()).unwrap_err();
+    assert_eq!(err.span, Span::new(5, 7));
+}
+
+#[test]
+fn lex_test_continuation() {
+    use crate::lexer::Token::*;
+    use crate::lexer::Operator::*;
+    let lit = |s: &str| Literal(s.as_bytes().to_vec());
+    assert_eq!(lex_plain("[1,\n2] +\n3\n4"), [OpenBracket, lit("1"), Comma, lit("2"), CloseBracket, Operator(Add), lit("3"), Newline, lit("4")]);
+    assert_eq!(lex_plain("1 # 2 (\n/* 3\n*/ 4 /**/"), [lit("1"), Newline, lit("4")]);
+    assert!(is_incomplete(b"f(1,\n2"));
+    assert!(is_incomplete(b"1 /* x"));
+    assert!(!is_incomplete(b"1)\n"));
+    assert_eq!(parse_tokens(b"1 /* 2").unwrap_err().span, Span::new(2, 6));
+}
+
+#[test]
+fn lex_test_hops() {
+    use crate::lexer::Token::*;
+    use crate::lexer::Operator::*;
+    let lit = |s: &str| Literal(s.as_bytes().to_vec());
+    assert_eq!(lex_plain("f = {1,\n2}"), [Identifier(b"f".to_vec()), Equals, OpenBrace, lit("1"), Comma, lit("2"), CloseBrace]);
+    assert_eq!(lex_plain("a := b"), [Identifier(b"a".to_vec()), Operator(DefineEqual), Identifier(b"b".to_vec())]);
+    assert!(is_incomplete(b"f =\n"));
+    assert!(is_incomplete(b"{1, 2"));
+}
@@ -1,5 +1,3 @@
-#![feature(bigint_helper_methods)]
-
 #[macro_use]
 mod macros;
 
@@ -7,10 +5,17 @@ mod powerseries;
 mod matrix;
 mod fixedseq;
 mod series;
+mod bivariate;
+mod laurent;
 mod coeff;
+mod fastmul;
 mod mathtypes;
 mod lexer;
 mod parser;
+mod help;
+mod arith;
+mod hops;
+mod error;
 pub mod runtime;
 pub mod lll;
 pub mod interpolate;
@@ -18,6 +23,11 @@ pub mod oeis;
 pub use mathtypes::{Ring, Field};
 pub use powerseries::PowerSeries;
 pub use coeff::{ModIntP32, MersP31, MersP61};
-pub use fixedseq::ShortSeq;
-pub use series::Series;
+pub use fastmul::FastMul;
+pub use fixedseq::{FixedSeq, ShortSeq};
+pub use series::{Series, SeriesFormat};
+pub use bivariate::BiSeries;
+pub use laurent::Laurent;
 pub use matrix::Matrix;
+pub use lexer::{ParseError, Span};
+pub use error::PerqError;
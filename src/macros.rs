@@ -377,7 +377,8 @@ macro_rules! binop_promotion {
                             let mut ser = Series::promote(d);
                             ser.expand_to(s.accuracy());
                             Ok(SeriesExpr($op(s, ser)))
-                        }
+                        },
+                        _ => Err(PerqError::InvalidArgument("Operator only applies to series and constants.".to_string()))
                     }
                 },
                 ConstExpr(c) => {
@@ -389,9 +390,11 @@ macro_rules! binop_promotion {
                         },
                         ConstExpr(d) => {
                             Ok(ConstExpr($op(c, d)))
-                        }
+                        },
+                        _ => Err(PerqError::InvalidArgument("Operator only applies to series and constants.".to_string()))
                     }
-                }
+                },
+                _ => Err(PerqError::InvalidArgument("Operator only applies to series and constants.".to_string()))
             }
         }
     };
@@ -1,13 +1,216 @@
-use perq::oeis;
-use std::io::BufRead;
-use perq::{Series, PowerSeries, Matrix, lll};
-use perq::runtime;
-use rug::Integer;
-
-fn main() {
-    let stdin = std::io::stdin();
+use perq::{runtime, PerqError};
+use rustyline::completion::Completer;
+use rustyline::error::ReadlineError;
+use rustyline::highlight::Highlighter;
+use rustyline::hint::Hinter;
+use rustyline::history::DefaultHistory;
+use rustyline::validate::{ValidationContext, ValidationResult, Validator};
+use rustyline::{Context, Editor, Helper};
+use std::io::IsTerminal;
+use std::process::ExitCode;
+
+const USAGE: &str = "usage: perq [--db FILE] [--workspace FILE] [--prelude FILE]... [-e EXPR]... [SCRIPT]
+
+Without a script or expression an interactive session is started on stdin,
+with line editing and history kept in ~/.perq_history when stdin is a terminal.
+
+  -e, --eval EXPR       run EXPR, may be given several times
+  -d, --db FILE         sequence database to search, defaults to \"stripped\"
+  -w, --workspace FILE  load a workspace written by save(\"FILE\") before anything else
+  -p, --prelude FILE    run FILE first, for shared definitions, may be given several times
+  -h, --help            print this message";
+
+struct Options {
+    database: String,
+    workspace: Option<String>,
+    preludes: Vec<String>,
+    exprs: Vec<String>,
+    script: Option<String>,
+}
+
+fn parse_args(mut args: impl Iterator<Item=String>) -> Result<Option<Options>, String> {
+    let mut opts = Options { database: "stripped".to_string(), workspace: None, preludes: vec![], exprs: vec![], script: None };
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "-h" | "--help" => { return Ok(None); },
+            "-e" | "--eval" => {
+                let Some(expr) = args.next() else {
+                    return Err(format!("{} expects an expression.", arg));
+                };
+                opts.exprs.push(expr);
+            },
+            "-d" | "--db" => {
+                let Some(file) = args.next() else {
+                    return Err(format!("{} expects a file name.", arg));
+                };
+                opts.database = file;
+            },
+            "-w" | "--workspace" => {
+                let Some(file) = args.next() else {
+                    return Err(format!("{} expects a file name.", arg));
+                };
+                opts.workspace = Some(file);
+            },
+            "-p" | "--prelude" => {
+                let Some(file) = args.next() else {
+                    return Err(format!("{} expects a file name.", arg));
+                };
+                opts.preludes.push(file);
+            },
+            _ if arg.starts_with('-') => { return Err(format!("Unknown option {}.", arg)); },
+            _ => {
+                if opts.script.is_some() {
+                    return Err("Only one script can be run.".to_string());
+                }
+                opts.script = Some(arg);
+            }
+        }
+    }
+    Ok(Some(opts))
+}
+
+// Exit codes: 0 on success, 1 if a statement failed, 2 on bad usage or unreadable files
+fn main() -> ExitCode {
+    let opts = match parse_args(std::env::args().skip(1)) {
+        Ok(Some(opts)) => opts,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return ExitCode::SUCCESS;
+        },
+        Err(msg) => {
+            eprintln!("{}\n{}", msg, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+    let mut rt = match runtime::RunTimeEnvironment::new(opts.database.clone()) {
+        Ok(rt) => rt,
+        Err(err) => {
+            eprintln!("Failed to load database {}: {}", opts.database, err);
+            return ExitCode::from(2);
+        }
+    };
+    if let Some(workspace) = &opts.workspace {
+        if let Err(err) = rt.load(workspace) {
+            eprintln!("Failed to load workspace {}: {}", workspace, err);
+            return ExitCode::from(if matches!(err, PerqError::Io(_)) { 2 } else { 1 });
+        }
+    }
+    let mut stdout = std::io::stdout();
+    for prelude in &opts.preludes {
+        if let Err(code) = run_file(&mut rt, prelude, &mut stdout) {
+            return code;
+        }
+    }
+    if opts.exprs.is_empty() && opts.script.is_none() {
+        if std::io::stdin().is_terminal() {
+            return interactive(rt);
+        }
+        let mut bufin = std::io::BufReader::new(std::io::stdin());
+        return match rt.repl(&mut bufin, &mut stdout, true) {
+            Ok(true) => ExitCode::SUCCESS,
+            Ok(false) => ExitCode::FAILURE,
+            Err(_) => ExitCode::from(2),
+        };
+    }
+    for text in &opts.exprs {
+        if let Err(code) = run(&mut rt, text, &mut stdout) {
+            return code;
+        }
+    }
+    if let Some(script) = opts.script {
+        if let Err(code) = run_file(&mut rt, &script, &mut stdout) {
+            return code;
+        }
+    }
+    ExitCode::SUCCESS
+}
+
+fn run(rt: &mut runtime::RunTimeEnvironment, text: &str, outp: &mut dyn std::io::Write) -> Result<(), ExitCode> {
+    match rt.run_text(text, outp) {
+        Ok(None) => Ok(()),
+        Ok(Some(diagnostic)) => {
+            eprintln!("{}", diagnostic);
+            Err(ExitCode::FAILURE)
+        },
+        Err(_) => Err(ExitCode::from(2))
+    }
+}
+
+fn run_file(rt: &mut runtime::RunTimeEnvironment, file: &str, outp: &mut dyn std::io::Write) -> Result<(), ExitCode> {
+    match std::fs::read_to_string(file) {
+        Ok(text) => run(rt, &text, outp),
+        Err(err) => {
+            eprintln!("Failed to read {}: {}", file, err);
+            Err(ExitCode::from(2))
+        }
+    }
+}
+
+// The editor owns the runtime so completion sees the current variables
+struct PerqHelper {
+    rt: runtime::RunTimeEnvironment,
+}
+
+impl Completer for PerqHelper {
+    type Candidate = String;
+
+    fn complete(&self, line: &str, pos: usize, _ctx: &Context<'_>) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).map_or(0, |i| i + 1);
+        Ok((start, self.rt.completions(&line[start..pos])))
+    }
+}
+
+impl Hinter for PerqHelper {
+    type Hint = String;
+}
+
+impl Highlighter for PerqHelper { }
+
+// Enter inserts a newline while the statement is unfinished
+impl Validator for PerqHelper {
+    fn validate(&self, ctx: &mut ValidationContext) -> rustyline::Result<ValidationResult> {
+        Ok(if runtime::is_incomplete(ctx.input()) { ValidationResult::Incomplete } else { ValidationResult::Valid(None) })
+    }
+}
+
+impl Helper for PerqHelper { }
+
+fn interactive(rt: runtime::RunTimeEnvironment) -> ExitCode {
+    let mut editor: Editor<PerqHelper, DefaultHistory> = match Editor::new() {
+        Ok(editor) => editor,
+        Err(err) => {
+            eprintln!("Failed to start line editor: {}", err);
+            return ExitCode::from(2);
+        }
+    };
+    editor.set_helper(Some(PerqHelper { rt }));
+    let history = std::env::var_os("HOME").map(|home| std::path::PathBuf::from(home).join(".perq_history"));
+    if let Some(file) = &history {
+        // Missing on first start
+        let _ = editor.load_history(file);
+    }
     let mut stdout = std::io::stdout();
-    let mut bufin = std::io::BufReader::new(stdin);
-    let mut rt = runtime::RunTimeEnvironment::new("stripped".to_string()).unwrap();
-    rt.repl(&mut bufin, &mut stdout, true);
+    loop {
+        let line = match editor.readline("> ") {
+            Ok(line) => line,
+            Err(ReadlineError::Interrupted) => { continue; },
+            Err(ReadlineError::Eof) => { return ExitCode::SUCCESS; },
+            Err(err) => {
+                eprintln!("{}", err);
+                return ExitCode::from(2);
+            }
+        };
+        if !line.trim().is_empty() {
+            let _ = editor.add_history_entry(line.as_str());
+            if let Some(file) = &history {
+                let _ = editor.save_history(file);
+            }
+        }
+        let Some(helper) = editor.helper_mut() else { unreachable!() };
+        match helper.rt.run_text(&line, &mut stdout) {
+            Ok(None) => { },
+            Ok(Some(diagnostic)) => { println!("{}", diagnostic); },
+            Err(_) => { return ExitCode::from(2); }
+        }
+    }
 }
@@ -5,6 +5,7 @@ use std::collections::BTreeMap;
 use std::sync::Mutex;
 use rustc_hash::FxHashMap;
 use std::io::BufRead;
+use crate::error::PerqError;
 use rayon::iter::{ParallelIterator, IntoParallelIterator};
 
 const BINOP_NUM: usize = 10;
@@ -142,20 +143,21 @@ pub struct SeqDB {
     pub ind_to_a: Vec<u32>
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub struct SearchResult {
-    score: i32,
-    series: Series,
-    description: String
+    pub score: i32,
+    pub series: Series,
+    pub formula: String,
+    pub a_number: u32
 }
 
 impl std::fmt::Display for SearchResult {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}: {}", self.score, self.description)
+        write!(f, "{}: {} matches A{}", self.score, self.formula, self.a_number)
     }
 }
 
-#[derive(Debug, Default)]
+#[derive(Debug, Clone, Default)]
 pub struct TopResults {
     results: Vec<SearchResult>
 }
@@ -166,13 +168,29 @@ impl TopResults {
         self.results.sort_by(|a, b| b.score.cmp(&a.score));
         if self.results.len() > 10 { self.results.pop(); }
     }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn get(&self, i: usize) -> Option<&SearchResult> {
+        self.results.get(i)
+    }
 }
 
 // TODO: Make top shown editable
 impl std::fmt::Display for TopResults {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.results.is_empty() {
+            return write!(f, "No results found.");
+        }
         for i in 0..std::cmp::min(10, self.results.len()) {
-            write!(f, "{}\n", self.results[i])?;
+            if i > 0 { writeln!(f)?; }
+            write!(f, "[{}] {}", i, self.results[i])?;
         }
         Ok(())
     }
@@ -194,11 +212,15 @@ impl SeqDB {
         true
     }
 
-    fn add_entry(&mut self, anum: u32, seq: &str) {
+    fn add_entry(&mut self, anum: u32, seq: &str) -> Result<(), String> {
         let cur_ind = self.a_to_ind.len();
-        let short_seq: ShortSeq<MersP31> = seq.parse().unwrap();
-        if !Self::significant(short_seq) { return; }
-        let long_seq: Series = seq.parse().unwrap();
+        let Ok(short_seq) = seq.parse::<ShortSeq<MersP31>>() else {
+            return Err(format!("Malformed terms for A{:06}.", anum));
+        };
+        if !Self::significant(short_seq) { return Ok(()); }
+        let Ok(long_seq) = seq.parse::<Series>() else {
+            return Err(format!("Malformed terms for A{:06}.", anum));
+        };
         if self.short_map.get(&short_seq).is_none() {
             self.short_map.insert(short_seq, anum);
             self.short_vec.push(short_seq);
@@ -206,21 +228,34 @@ impl SeqDB {
             self.a_to_ind.insert(anum, cur_ind);
             self.ind_to_a.push(anum);
         }
+        Ok(())
     }
 
-    pub fn from_stripped(filename: String) -> std::io::Result<Self> {
+    pub fn from_stripped(filename: String) -> Result<Self, PerqError> {
         let file = std::fs::File::open(filename)?;
-        let reader = std::io::BufReader::new(file);
+        Self::from_reader(std::io::BufReader::new(file))
+    }
+
+    // Lines look like "A000045 ,0,1,1,2,3,5,8,", comments start with #
+    pub fn from_reader(reader: impl BufRead) -> Result<Self, PerqError> {
         let mut db = Self::new();
 
-        for line in reader.lines() {
+        for (i, line) in reader.lines().enumerate() {
             let line = line?;
             if line.starts_with('#') {
                 continue;
             }
-            let (apart, seqpart) = line.split_once(" ,").unwrap();
-            let anum: u32 = apart[1..].trim_start_matches('0').parse().unwrap();
-            db.add_entry(anum, &seqpart[..seqpart.len() - 1]);
+            let format_err = |msg: &str| PerqError::Database { line: i + 1, msg: msg.to_string() };
+            let Some((apart, seqpart)) = line.split_once(" ,") else {
+                return Err(format_err("Expected A-number followed by \" ,\"."));
+            };
+            let Some(anum) = apart.strip_prefix('A').and_then(|a| a.parse::<u32>().ok()) else {
+                return Err(format_err("Malformed A-number."));
+            };
+            let Some(terms) = seqpart.strip_suffix(',') else {
+                return Err(format_err("Expected terms to end with \",\"."));
+            };
+            db.add_entry(anum, terms).map_err(|msg| format_err(&msg))?;
         }
 
         Ok(db)
@@ -251,7 +286,7 @@ impl SeqDB {
         top.into_inner().unwrap()
     }
 
-    fn pretty_result(&self, in_pre: usize, out_pre: usize, u_op: usize, seq: usize, b_op: usize, ind: usize) -> String {
+    fn pretty_result(&self, in_pre: usize, out_pre: usize, u_op: usize, seq: usize, b_op: usize) -> String {
         let p_format = preop_format("INPUT", in_pre);
         let u_format = unop_format(&p_format, u_op);
         let b_format = match seq {
@@ -261,7 +296,7 @@ impl SeqDB {
                 binop_format(&u_format, b_op, &seqname)
             }
         };
-        format!("{} matches A{}", preop_format(&b_format, out_pre), self.ind_to_a[ind])
+        preop_format(&b_format, out_pre)
     }
 
     fn calculate_long(&self, long_inp: &Series, pre_op: usize, post_op: usize, u_op: usize, b_op: usize, seq: usize) -> Option<Series> {
@@ -310,7 +345,8 @@ impl SeqDB {
                     top.lock().expect("Mutex failed").add_result(SearchResult {
                         score: cur_score,
                         series: long_res.clone(),
-                        description: self.pretty_result(pre_op, k, u_op, seq, b_op, ind),
+                        formula: self.pretty_result(pre_op, k, u_op, seq, b_op),
+                        a_number: self.ind_to_a[ind],
                     });
                 }
             }
@@ -1,221 +1,499 @@
-use crate::lexer::{Token, Operator};
+use crate::lexer::{Token, SpannedToken, Operator, Span, ParseError};
 
-// TODO: Fix unary operator fuckery (due to split)
-// TODO: Fix right vs left associative (right chunk)
+// Precedence climbing parser. Binary operators from loosest to tightest:
+//
+//   +  -            left associative
+//   *  /  .*  ./    left associative
+//   -  (unary)      prefix, so -a*b is (-a)*b and -a^b is -(a^b)
+//   @               right associative
+//   ^               right associative, the exponent may carry its own sign as in a^-1
+//   !  (factorial)  postfix, binds like indexing so -n! is -(n!) and 2^n! is 2^(n!)
+//
+// Function application f(a, b) and juxtaposition f a bind tighter than any operator.
+// A bracket directly after a value indexes it, s[n] or s[a..b], while f [a] is an application.
 
 #[derive(Debug, Clone, PartialEq)]
-pub enum SyntaxNode {
+pub enum NodeKind {
     Constant(Vec<u8>),
     Identifier(Vec<u8>),
+    Text(Vec<u8>),
     Series(Vec<SyntaxNode>),
     Paren(Box<SyntaxNode>),
     LetStatement(Vec<u8>, Box<SyntaxNode>),
     RecStatement(Box<SyntaxNode>, Box<SyntaxNode>),
+    // Function name, parameter names and body
+    DefStatement(Vec<u8>, Vec<Vec<u8>>, Box<SyntaxNode>),
     Application(Vec<u8>, Vec<SyntaxNode>),
+    Index(Box<SyntaxNode>, Box<SyntaxNode>),
+    // Either bound may be left out as in s[..b]
+    Slice(Box<SyntaxNode>, Option<Box<SyntaxNode>>, Option<Box<SyntaxNode>>),
     BinaryOp(Box<SyntaxNode>, Operator, Box<SyntaxNode>),
     UnaryOp(Operator, Box<SyntaxNode>),
 }
 
-type ParserFunction = fn(&[Token]) -> Result<SyntaxNode, String>;
+#[derive(Debug, Clone, PartialEq)]
+pub struct SyntaxNode {
+    pub kind: NodeKind,
+    pub span: Span,
+}
+
+impl SyntaxNode {
+    fn new(kind: NodeKind, span: Span) -> Self {
+        Self { kind, span }
+    }
+}
+
+// Statements are separated by newlines or semicolons, a semicolon silences the output
+#[derive(Debug, Clone, PartialEq)]
+pub struct Statement {
+    pub node: SyntaxNode,
+    pub silent: bool,
+}
+
+const UNARY_PRECEDENCE: u8 = 3;
+
+// (precedence, right associative)
+fn binary_precedence(op: Operator) -> Option<(u8, bool)> {
+    match op {
+        Operator::Add | Operator::Sub => Some((1, false)),
+        Operator::Mul | Operator::Div | Operator::PointMul | Operator::PointDiv => Some((2, false)),
+        Operator::Compose => Some((4, true)),
+        Operator::Pow => Some((5, true)),
+        Operator::DefineEqual | Operator::Factorial => None,
+    }
+}
 
-fn check_unary_op(tok: &[Token], op: Operator, subroutine: ParserFunction) -> Result<Option<SyntaxNode>, String> {
-    Ok(if tok[0] == Token::Operator(op) {
-        Some(subroutine(&tok[1..])?)
-    } else {
-        None
-    })
+struct Parser<'a> {
+    tok: &'a [SpannedToken],
+    pos: usize,
+    // Where to point when input runs out
+    end: Span,
 }
 
-fn right_binary_chunk<const N: usize>(tok: &[Token], ops: [Operator; N], subroutine: ParserFunction) -> Result<SyntaxNode, String> {
-    let mut depth = 0;
-    let mut op_loc = vec![];
-    for i in 0..tok.len() {
-        if tok[i] == Token::OpenParen {
-            depth += 1;
+impl<'a> Parser<'a> {
+    fn peek(&self) -> Option<&'a Token> {
+        self.tok.get(self.pos).map(|t| &t.token)
+    }
+
+    fn next(&mut self) -> Option<&'a SpannedToken> {
+        let res = self.tok.get(self.pos);
+        self.pos += 1;
+        res
+    }
+
+    fn error(&self, expected: &str, found: Option<&SpannedToken>) -> ParseError {
+        match found {
+            Some(t) => ParseError::new(format!("Expected {}, found {}", expected, t.token), t.span),
+            None => ParseError::new(format!("Expected {}, found end of input", expected), self.end)
         }
-        if tok[i] == Token::OpenBracket {
-            depth += 1;
+    }
+
+    fn expect(&mut self, expected: Token, what: &str) -> Result<Span, ParseError> {
+        match self.next() {
+            Some(t) if t.token == expected => Ok(t.span),
+            found => Err(self.error(what, found))
         }
-        if depth == 0 {
-            for j in 0..N {
-                if tok[i] == Token::Operator(ops[j]) {
-                    op_loc.push((i, j));
-                }
+    }
+
+    fn parse_expression(&mut self, min_prec: u8) -> Result<SyntaxNode, ParseError> {
+        let mut lhs = self.parse_prefix()?;
+        while let Some(Token::Operator(op)) = self.peek() {
+            let Some((prec, right)) = binary_precedence(*op) else { break; };
+            if prec < min_prec {
+                break;
             }
+            self.pos += 1;
+            let rhs = self.parse_expression(if right { prec } else { prec + 1 })?;
+            let span = lhs.span.join(rhs.span);
+            lhs = SyntaxNode::new(NodeKind::BinaryOp(Box::new(lhs), *op, Box::new(rhs)), span);
         }
-        if tok[i] == Token::CloseParen {
-            depth -= 1;
+        Ok(lhs)
+    }
+
+    fn parse_prefix(&mut self) -> Result<SyntaxNode, ParseError> {
+        if self.peek() == Some(&Token::Operator(Operator::Sub)) {
+            let op_span = self.next().unwrap().span;
+            let operand = self.parse_expression(UNARY_PRECEDENCE + 1)?;
+            let span = op_span.join(operand.span);
+            return Ok(SyntaxNode::new(NodeKind::UnaryOp(Operator::Sub, Box::new(operand)), span));
         }
-        if tok[i] == Token::CloseBracket {
-            depth -= 1;
+        self.parse_primary()
+    }
+
+    fn starts_primary(tok: Option<&Token>) -> bool {
+        matches!(tok, Some(Token::Identifier(_) | Token::Literal(_) | Token::Text(_) | Token::OpenBracket | Token::OpenBrace))
+    }
+
+    // Comma separated expressions up to the closing token, returns the span of the closing token
+    fn parse_list(&mut self, close: Token, what: &str) -> Result<(Vec<SyntaxNode>, Span), ParseError> {
+        let mut res = vec![];
+        if self.peek() == Some(&close) {
+            return Ok((res, self.next().unwrap().span));
+        }
+        loop {
+            res.push(self.parse_expression(0)?);
+            match self.next() {
+                Some(SpannedToken { token: Token::Comma, .. }) => { },
+                Some(t) if t.token == close => { return Ok((res, t.span)); },
+                found => { return Err(self.error(what, found)); }
+            }
         }
     }
-    if op_loc.len() == 0 {
-        return subroutine(tok);
+
+    fn index_follows(&self, span: Span) -> bool {
+        self.tok.get(self.pos).is_some_and(|t| t.token == Token::OpenBracket && t.span.start == span.end)
     }
-    let mut cur_node = subroutine(&tok[op_loc[op_loc.len() - 1].0+1..])?;
-    while op_loc.len() > 1 {
-        let lhs = subroutine(&tok[op_loc[op_loc.len() - 2].0+1..op_loc[op_loc.len() - 1].0])?;
-        cur_node = SyntaxNode::BinaryOp(Box::new(lhs), ops[op_loc[op_loc.len() - 1].1], Box::new(cur_node));
-        op_loc.pop();
+
+    fn parse_primary(&mut self) -> Result<SyntaxNode, ParseError> {
+        let mut node = self.parse_atom()?;
+        loop {
+            if self.peek() == Some(&Token::Operator(Operator::Factorial)) {
+                let span = node.span.join(self.next().unwrap().span);
+                node = SyntaxNode::new(NodeKind::UnaryOp(Operator::Factorial, Box::new(node)), span);
+                continue;
+            }
+            if !self.index_follows(node.span) {
+                break;
+            }
+            self.pos += 1;
+            let start = node.span;
+            let lo = match self.peek() {
+                Some(Token::DotDot) => None,
+                _ => Some(Box::new(self.parse_expression(0)?))
+            };
+            let kind = if self.peek() == Some(&Token::DotDot) {
+                self.pos += 1;
+                let hi = match self.peek() {
+                    Some(Token::CloseBracket) => None,
+                    _ => Some(Box::new(self.parse_expression(0)?))
+                };
+                NodeKind::Slice(Box::new(node), lo, hi)
+            } else {
+                let Some(ind) = lo else { unreachable!() };
+                NodeKind::Index(Box::new(node), ind)
+            };
+            let close = self.expect(Token::CloseBracket, "] or .. in index")?;
+            node = SyntaxNode::new(kind, start.join(close));
+        }
+        Ok(node)
     }
-    if op_loc.len() == 1 {
-        let lhs = subroutine(&tok[..op_loc[0].0])?;
-        cur_node = SyntaxNode::BinaryOp(Box::new(lhs), ops[op_loc[0].1], Box::new(cur_node));
+
+    fn parse_atom(&mut self) -> Result<SyntaxNode, ParseError> {
+        let Some(first) = self.next() else {
+            return Err(self.error("expression", None));
+        };
+        match &first.token {
+            Token::Literal(x) => Ok(SyntaxNode::new(NodeKind::Constant(x.to_vec()), first.span)),
+            Token::Text(x) => Ok(SyntaxNode::new(NodeKind::Text(x.to_vec()), first.span)),
+            Token::Identifier(x) => {
+                if self.peek() == Some(&Token::OpenParen) {
+                    self.pos += 1;
+                    let (args, close) = self.parse_list(Token::CloseParen, ", or ) in argument list")?;
+                    Ok(SyntaxNode::new(NodeKind::Application(x.to_vec(), args), first.span.join(close)))
+                } else if Self::starts_primary(self.peek()) && !self.index_follows(first.span) {
+                    let arg = self.parse_primary()?;
+                    let span = first.span.join(arg.span);
+                    Ok(SyntaxNode::new(NodeKind::Application(x.to_vec(), vec![arg]), span))
+                } else {
+                    Ok(SyntaxNode::new(NodeKind::Identifier(x.to_vec()), first.span))
+                }
+            },
+            Token::OpenParen => {
+                let inner = self.parse_expression(0)?;
+                let close = self.expect(Token::CloseParen, ")")?;
+                Ok(SyntaxNode::new(NodeKind::Paren(Box::new(inner)), first.span.join(close)))
+            },
+            Token::OpenBracket => {
+                let (coeffs, close) = self.parse_list(Token::CloseBracket, ", or ] in series")?;
+                Ok(SyntaxNode::new(NodeKind::Series(coeffs), first.span.join(close)))
+            },
+            Token::OpenBrace => {
+                let (coeffs, close) = self.parse_list(Token::CloseBrace, ", or } in series")?;
+                Ok(SyntaxNode::new(NodeKind::Series(coeffs), first.span.join(close)))
+            },
+            _ => Err(self.error("expression", Some(first)))
+        }
+    }
+
+    fn parse_command(&mut self) -> Result<SyntaxNode, ParseError> {
+        let start = self.tok[self.pos].span;
+        Ok(match self.peek() {
+            Some(Token::Let) => {
+                self.pos += 1;
+                let lhs = match self.next() {
+                    Some(SpannedToken { token: Token::Identifier(lhs), .. }) => lhs,
+                    found => { return Err(self.error("identifier after let", found)); }
+                };
+                self.expect(Token::Operator(Operator::DefineEqual), ":= after let")?;
+                let rhs = self.parse_expression(0)?;
+                let span = start.join(rhs.span);
+                SyntaxNode::new(NodeKind::LetStatement(lhs.to_vec(), Box::new(rhs)), span)
+            },
+            Some(Token::LetRec) => {
+                self.pos += 1;
+                let lhs = self.parse_expression(0)?;
+                self.expect(Token::Operator(Operator::DefineEqual), ":= after letrec")?;
+                let rhs = self.parse_expression(0)?;
+                let span = start.join(rhs.span);
+                SyntaxNode::new(NodeKind::RecStatement(Box::new(lhs), Box::new(rhs)), span)
+            },
+            Some(Token::Def) => {
+                self.pos += 1;
+                let name = match self.next() {
+                    Some(SpannedToken { token: Token::Identifier(name), .. }) => name,
+                    found => { return Err(self.error("function name after def", found)); }
+                };
+                self.expect(Token::OpenParen, "( after function name")?;
+                let mut params = vec![];
+                if self.peek() == Some(&Token::CloseParen) {
+                    self.pos += 1;
+                } else {
+                    loop {
+                        match self.next() {
+                            Some(SpannedToken { token: Token::Identifier(param), .. }) => { params.push(param.to_vec()); },
+                            found => { return Err(self.error("parameter name", found)); }
+                        }
+                        match self.next() {
+                            Some(SpannedToken { token: Token::Comma, .. }) => { },
+                            Some(SpannedToken { token: Token::CloseParen, .. }) => { break; },
+                            found => { return Err(self.error(", or ) in parameter list", found)); }
+                        }
+                    }
+                }
+                self.expect(Token::Operator(Operator::DefineEqual), ":= after parameter list")?;
+                let body = self.parse_expression(0)?;
+                let span = start.join(body.span);
+                SyntaxNode::new(NodeKind::DefStatement(name.to_vec(), params, Box::new(body)), span)
+            },
+            // The HOPS equation f = expr is solved like letrec f := expr
+            Some(Token::Identifier(_)) if self.tok.get(self.pos + 1).is_some_and(|t| t.token == Token::Equals) => {
+                let lhs = self.parse_atom()?;
+                self.pos += 1;
+                let rhs = self.parse_expression(0)?;
+                let span = start.join(rhs.span);
+                SyntaxNode::new(NodeKind::RecStatement(Box::new(lhs), Box::new(rhs)), span)
+            },
+            _ => self.parse_expression(0)?
+        })
     }
-    Ok(cur_node)
 }
 
-fn split_on_delim(tok: &[Token], delim: Token, subroutine: ParserFunction) -> Result<Vec<SyntaxNode>, String> {
-    let mut res = vec![];
-    let mut i = 0;
-    let mut depth = 0;
-    while i < tok.len() {
-        let mut j = i;
-        while j < tok.len() && (tok[j] != delim || depth > 0) { 
-            if tok[j] == Token::OpenParen || tok[j] == Token::OpenBracket { depth += 1; }
-            if tok[j] == Token::CloseParen || tok[j] == Token::CloseBracket { depth -= 1; }
-            j += 1; 
+// Source text that parses back to the same tree, used to save definitions
+impl std::fmt::Display for SyntaxNode {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let name = |x: &[u8]| String::from_utf8_lossy(x).into_owned();
+        let list = |xs: &[SyntaxNode]| xs.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(", ");
+        match &self.kind {
+            NodeKind::Constant(x) | NodeKind::Identifier(x) => write!(f, "{}", name(x)),
+            NodeKind::Text(x) => write!(f, "\"{}\"", name(x)),
+            NodeKind::Series(xs) => write!(f, "[{}]", list(xs)),
+            NodeKind::Paren(x) => write!(f, "({})", x),
+            NodeKind::LetStatement(lhs, rhs) => write!(f, "let {} := {}", name(lhs), rhs),
+            NodeKind::RecStatement(lhs, rhs) => write!(f, "letrec {} := {}", lhs, rhs),
+            NodeKind::DefStatement(func, params, body) => {
+                let params: Vec<String> = params.iter().map(|p| name(p)).collect();
+                write!(f, "def {}({}) := {}", name(func), params.join(", "), body)
+            },
+            NodeKind::Application(func, args) => write!(f, "{}({})", name(func), list(args)),
+            NodeKind::Index(x, i) => write!(f, "{}[{}]", x, i),
+            NodeKind::Slice(x, lo, hi) => {
+                write!(f, "{}[", x)?;
+                if let Some(lo) = lo { write!(f, "{}", lo)?; }
+                write!(f, "..")?;
+                if let Some(hi) = hi { write!(f, "{}", hi)?; }
+                write!(f, "]")
+            },
+            NodeKind::BinaryOp(x, op, y) => write!(f, "{} {} {}", x, op, y),
+            NodeKind::UnaryOp(Operator::Factorial, x) => write!(f, "{}!", x),
+            NodeKind::UnaryOp(op, x) => write!(f, "{}{}", op, x),
         }
-        res.push(subroutine(&tok[i..j])?);
-        i = j + 1;
     }
-    Ok(res)
 }
 
-fn parse_constant_literal(tok: &[Token]) -> Result<SyntaxNode, String> {
-    if tok.len() != 1 {
-        return Err("Expected single constant literal".to_string());
-    }
-    match &tok[0] {
-        Token::Literal(x) => Ok(SyntaxNode::Constant(x.to_vec())),
-        _ => Err("Expected literal".to_string())
+pub fn parse_commands(tok: &[SpannedToken]) -> Result<Vec<Statement>, ParseError> {
+    let mut command_nodes = vec![];
+    let mut rest = tok;
+    while !rest.is_empty() {
+        let len = rest.iter().position(|t| t.token == Token::Semicolon || t.token == Token::Newline).unwrap_or(rest.len());
+        let (stmt, tail) = rest.split_at(len);
+        let silent = tail.first().is_some_and(|t| t.token == Token::Semicolon);
+        rest = tail.get(1..).unwrap_or_default();
+        let Some(last) = stmt.last() else {
+            continue;
+        };
+        let mut parser = Parser { tok: stmt, pos: 0, end: Span::new(last.span.end, last.span.end + 1) };
+        let node = parser.parse_command()?;
+        if let Some(extra) = parser.next() {
+            return Err(parser.error("operator or end of statement", Some(extra)));
+        }
+        command_nodes.push(Statement { node, silent });
     }
+    Ok(command_nodes)
 }
 
-fn parse_constant_operand(tok: &[Token]) -> Result<SyntaxNode, String> {
-    match check_unary_op(tok, Operator::Sub, parse_constant_operand)? {
-        Some(res) => Ok(SyntaxNode::UnaryOp(Operator::Sub, Box::new(res))),
-        None => right_binary_chunk(tok, [Operator::Pow], parse_constant_literal)
+#[test]
+fn parser_test_1() {
+    use crate::parser::NodeKind::*;
+    let tok = crate::lexer::parse_tokens("let x := [7, 2, 3]".as_bytes()).unwrap();
+    let res: Vec<SyntaxNode> = crate::parser::parse_commands(&tok).unwrap().into_iter().map(|s| s.node).collect();
+    let node = |kind, start, end| SyntaxNode::new(kind, Span::new(start, end));
+    let expected = [
+        node(LetStatement(vec![b'x'],
+            Box::new(node(Series(vec![
+                node(Constant(vec![b'7']), 10, 11),
+                node(Constant(vec![b'2']), 13, 14),
+                node(Constant(vec![b'3']), 16, 17)
+            ]), 9, 18))
+        ), 0, 18)
+    ];
+    assert!(res == expected);
+}
+
+#[cfg(test)]
+fn parse_one(text: &str) -> SyntaxNode {
+    let tok = crate::lexer::parse_tokens(text.as_bytes()).unwrap();
+    let mut res = crate::parser::parse_commands(&tok).unwrap();
+    assert_eq!(res.len(), 1);
+    res.pop().unwrap().node
+}
+
+#[cfg(test)]
+fn show(node: &SyntaxNode) -> String {
+    use crate::parser::NodeKind::*;
+    let op_str = |op: &Operator| op.to_string();
+    match &node.kind {
+        Constant(x) | Identifier(x) | Text(x) => String::from_utf8(x.clone()).unwrap(),
+        Series(xs) => format!("[{}]", xs.iter().map(show).collect::<Vec<_>>().join(",")),
+        Paren(x) => show(x),
+        Application(f, xs) => format!("{}({})", String::from_utf8(f.clone()).unwrap(), xs.iter().map(show).collect::<Vec<_>>().join(",")),
+        Index(x, i) => format!("{}[{}]", show(x), show(i)),
+        Slice(x, lo, hi) => format!("{}[{}..{}]", show(x), lo.as_ref().map_or(String::new(), |n| show(n)), hi.as_ref().map_or(String::new(), |n| show(n))),
+        BinaryOp(x, op, y) => format!("({}{}{})", show(x), op_str(op), show(y)),
+        UnaryOp(Operator::Factorial, x) => format!("({}!)", show(x)),
+        UnaryOp(op, x) => format!("({}{})", op_str(op), show(x)),
+        LetStatement(_, _) | RecStatement(_, _) | DefStatement(_, _, _) => unreachable!(),
     }
 }
 
-fn parse_constant_factor(tok: &[Token]) -> Result<SyntaxNode, String> {
-    right_binary_chunk(tok, [Operator::Mul, Operator::Div], parse_constant_operand)
+#[test]
+fn parser_test_associativity() {
+    assert_eq!(show(&parse_one("a - b - c")), "((a-b)-c)");
+    assert_eq!(show(&parse_one("a / b * c")), "((a/b)*c)");
+    assert_eq!(show(&parse_one("a ^ b ^ c")), "(a^(b^c))");
+    assert_eq!(show(&parse_one("a @ b @ c")), "(a@(b@c))");
+    assert_eq!(show(&parse_one("1 - 2 + 3 - 4")), "(((1-2)+3)-4)");
+}
+
+#[test]
+fn parser_test_precedence() {
+    assert_eq!(show(&parse_one("a + b * c ^ d")), "(a+(b*(c^d)))");
+    assert_eq!(show(&parse_one("(a + b) * c")), "((a+b)*c)");
+    assert_eq!(show(&parse_one("a .* b + c ./ d")), "((a.*b)+(c./d))");
+    assert_eq!(show(&parse_one("f @ g ^ 2")), "(f@(g^2))");
+    assert_eq!(show(&parse_one("sqrt(a, b + c) * d")), "(sqrt(a,(b+c))*d)");
+    assert_eq!(show(&parse_one("derive a ^ 2")), "(derive(a)^2)");
 }
 
-fn parse_constant_summand(tok: &[Token]) -> Result<SyntaxNode, String> {
-    right_binary_chunk(tok, [Operator::Add, Operator::Sub], parse_constant_factor)
+#[test]
+fn parser_test_unary_minus() {
+    assert_eq!(show(&parse_one("-a")), "(-a)");
+    assert_eq!(show(&parse_one("-a ^ 2")), "(-(a^2))");
+    assert_eq!(show(&parse_one("-a * b")), "((-a)*b)");
+    assert_eq!(show(&parse_one("a * -b")), "(a*(-b))");
+    assert_eq!(show(&parse_one("a - -b")), "(a-(-b))");
+    assert_eq!(show(&parse_one("a ^ -1")), "(a^(-1))");
+    assert_eq!(show(&parse_one("[-1, 2 - -3, -(4)]")), "[(-1),(2-(-3)),(-4)]");
+    assert_eq!(show(&parse_one("f(-a, -b)")), "f((-a),(-b))");
 }
 
-fn parse_expression(tok: &[Token]) -> Result<SyntaxNode, String> {
-    // options:
-    // num
-    // [num, num, num, ...]
-    // identifier
-    // identifier "(" expr , expr , expr ... ")"
-    // identifier expr
-    // "(" summand ")"
-    match &tok[0] {
-        Token::Identifier(x) => {
-            if tok.len() == 1 {
-                Ok(SyntaxNode::Identifier(x.to_vec()))
-            } else if tok[1] == Token::OpenParen {
-                let args = split_on_delim(&tok[2..tok.len()-1], Token::Comma, parse_summand)?;
-                Ok(SyntaxNode::Application(x.to_vec(), args))
-            } else {
-                let arg = parse_expression(&tok[1..])?;
-                Ok(SyntaxNode::Application(x.to_vec(), vec![arg]))
-            }
-        },
-        Token::OpenParen => {
-            parse_summand(&tok[1..tok.len()-1])
-        },
-        Token::OpenBracket => {
-            let coeffs = split_on_delim(&tok[1..tok.len()-1], Token::Comma, parse_constant_summand)?;
-            Ok(SyntaxNode::Series(coeffs))
-        },
-        _ => {
-            parse_constant_summand(tok)
-        }
-    }
+#[test]
+fn parser_test_factorial() {
+    assert_eq!(show(&parse_one("-n!")), "(-(n!))");
+    assert_eq!(show(&parse_one("2^n! * 3")), "((2^(n!))*3)");
+    assert_eq!(show(&parse_one("s[2]!!")), "((s[2]!)!)");
+    assert_eq!(show(&parse_one("f(n)! / (n - k)!")), "((f(n)!)/((n-k)!))");
 }
 
-fn parse_operand(tok: &[Token]) -> Result<SyntaxNode, String> {
-    match check_unary_op(tok, Operator::Sub, parse_operand)? {
-        Some(res) => Ok(SyntaxNode::UnaryOp(Operator::Sub, Box::new(res))),
-        None => right_binary_chunk(tok, [Operator::Pow, Operator::Compose], parse_expression)
+#[test]
+fn parser_test_errors() {
+    for text in ["a +", "(a", "f(a b", "[1, 2", "a b c )", "let := 3", "letrec f 3", "def f := 1", "def f(a b) := a", "def f(1) := 1", "def (a) := a"] {
+        let tok = crate::lexer::parse_tokens(text.as_bytes()).unwrap();
+        assert!(crate::parser::parse_commands(&tok).is_err(), "{}", text);
     }
 }
 
-fn parse_factor(tok: &[Token]) -> Result<SyntaxNode, String> {
-    right_binary_chunk(tok, [Operator::Mul, Operator::Div, Operator::PointMul, Operator::PointDiv], parse_operand)
+#[test]
+fn parser_test_diagnostics() {
+    let text = "let x := [1, 2;\nsqrt(a b + 1";
+    let tok = crate::lexer::parse_tokens(text.as_bytes()).unwrap();
+    let err = crate::parser::parse_commands(&tok).unwrap_err();
+    assert_eq!(err.render(text.as_bytes()), "let x := [1, 2;\n              ^\nLine 1, column 15: Expected , or ] in series, found end of input");
+    let tok = crate::lexer::parse_tokens("1 + * 2".as_bytes()).unwrap();
+    let err = crate::parser::parse_commands(&tok).unwrap_err();
+    assert_eq!(err.msg, "Expected expression, found *");
+    assert_eq!(err.span, Span::new(4, 5));
+    let tok = crate::lexer::parse_tokens("f(a) + g(b, c) d".as_bytes()).unwrap();
+    let err = crate::parser::parse_commands(&tok).unwrap_err();
+    assert_eq!(err.span, Span::new(15, 16));
 }
 
-fn parse_summand(tok: &[Token]) -> Result<SyntaxNode, String> {
-    right_binary_chunk(tok, [Operator::Add, Operator::Sub], parse_factor)
+#[test]
+fn parser_test_silent() {
+    let tok = crate::lexer::parse_tokens("a; b\nc;\n\nd".as_bytes()).unwrap();
+    let res = crate::parser::parse_commands(&tok).unwrap();
+    let shown: Vec<_> = res.iter().map(|s| (show(&s.node), s.silent)).collect();
+    assert_eq!(shown, [("a".to_string(), true), ("b".to_string(), false), ("c".to_string(), true), ("d".to_string(), false)]);
 }
 
-fn parse_command(tok: &[Token]) -> Result<SyntaxNode, String> {
-    if tok.len() == 0 {
-        return Err("Expected token, got nothing".to_string());
-    }
-    Ok(match tok[0] {
-        Token::Let => {
-            if tok.len() <= 3 {
-                return Err("Expected identifier := expression to follow let keyword".to_string());
-            }
-            let lhs = match &tok[1] {
-                Token::Identifier(x) => x,
-                _ => { return Err("Expected identifier expression to follow let keyword".to_string()); }
-            };
-            if tok[2] != Token::Operator(Operator::DefineEqual) {
-                return Err("Expected := to follow let keyword".to_string());
-            }
-            let rhs = parse_summand(&tok[3..])?;
-            SyntaxNode::LetStatement(lhs.to_vec(), Box::new(rhs))
-        },
-        Token::LetRec => {
-            let define_loc = match tok.iter().position(|x| x == &Token::Operator(Operator::DefineEqual)) {
-                Some(x) => x,
-                None => { return Err("Expected := to follow letrec keyword".to_string()); }
-            };
-            let lhs = parse_summand(&tok[1..define_loc])?;
-            let rhs = parse_summand(&tok[define_loc+1..])?;
-            SyntaxNode::RecStatement(Box::new(lhs), Box::new(rhs))
-        }
-        _ => { 
-            parse_summand(tok)?
-        }
-    })
+#[test]
+fn parser_test_def() {
+    use crate::parser::NodeKind::*;
+    let tok = crate::lexer::parse_tokens("def g(a, b) := exp_mul(a, b) * 2".as_bytes()).unwrap();
+    let res = crate::parser::parse_commands(&tok).unwrap();
+    let DefStatement(name, params, body) = &res[0].node.kind else { panic!() };
+    assert_eq!(name, b"g");
+    assert_eq!(params, &[b"a".to_vec(), b"b".to_vec()]);
+    assert_eq!(show(body), "(exp_mul(a,b)*2)");
+    let tok = crate::lexer::parse_tokens("def one() := 1".as_bytes()).unwrap();
+    let res = crate::parser::parse_commands(&tok).unwrap();
+    assert!(matches!(&res[0].node.kind, DefStatement(_, params, _) if params.is_empty()));
 }
 
-pub fn parse_commands(tok: &[Token]) -> Result<Vec<SyntaxNode>, String> {
-    let mut command_nodes = vec![];
-    let mut i = 0; 
-    while i < tok.len() {
-        let mut j = i;
-        while j < tok.len() && tok[j] != Token::Semicolon { j += 1; }
-        command_nodes.push(parse_command(&tok[i..j])?);
-        i = j;
-        if i < tok.len() && tok[i] == Token::Semicolon { i += 1; }
+#[test]
+fn parser_test_index() {
+    assert_eq!(show(&parse_one("s[3] + 1")), "(s[3]+1)");
+    assert_eq!(show(&parse_one("s[1..n+1]")), "s[1..(n+1)]");
+    assert_eq!(show(&parse_one("s[..4][2..]")), "s[..4][2..]");
+    assert_eq!(show(&parse_one("f(a)[0] * (a+b)[1]")), "(f(a)[0]*(a+b)[1])");
+    assert_eq!(show(&parse_one("[1, 2][0] ^ 2")), "([1,2][0]^2)");
+    assert_eq!(show(&parse_one("derive [1, 2]")), "derive([1,2])");
+    assert_eq!(show(&parse_one("derive a[2]")), "derive(a[2])");
+    assert_eq!(show(&parse_one("-s[1]")), "(-s[1])");
+    for text in ["s[1", "s[]", "s[1..2..3]", "s[1, 2]"] {
+        let tok = crate::lexer::parse_tokens(text.as_bytes()).unwrap();
+        assert!(crate::parser::parse_commands(&tok).is_err(), "{}", text);
     }
-    Ok(command_nodes)
 }
 
 #[test]
-fn parser_test_1() {
-    use crate::parser::SyntaxNode::*;
-    let tok = crate::lexer::parse_tokens("let x := [7, 2, 3]".as_bytes()).unwrap();
+fn parser_test_display() {
+    for text in ["a - (b - c) * -d ^ 2", "derive a[1..3] @ f(x, [1, -2])", "s[..n] ./ t[2..]", "load(\"w.perq\")", "-n! + (a + 1)!"] {
+        let printed = parse_one(text).to_string();
+        assert_eq!(show(&parse_one(&printed)), show(&parse_one(text)), "{}", printed);
+    }
+    let tok = crate::lexer::parse_tokens("def g(a, b) := exp_mul(a, b) + a".as_bytes()).unwrap();
     let res = crate::parser::parse_commands(&tok).unwrap();
-    let expected = [
-        LetStatement(vec![b'x'], 
-            Box::new(Series(vec![
-                Constant(vec![b'7']), 
-                Constant(vec![b'2']), 
-                Constant(vec![b'3'])
-            ]))
-        )
-    ];
-    assert!(res == expected);
+    assert_eq!(res[0].node.to_string(), "def g(a, b) := exp_mul(a, b) + a");
+}
+
+#[test]
+fn parser_test_hops() {
+    use crate::parser::NodeKind::*;
+    assert_eq!(show(&parse_one("BINOMIAL({1, 2}, 3)")), "BINOMIAL([1,2],3)");
+    assert_eq!(parse_one("f = 1 + x*f^2").to_string(), "letrec f := 1 + x * f ^ 2");
+    assert!(matches!(parse_one("A = {1, -1}").kind, RecStatement(..)));
+    for text in ["f = ", "1 = f", "{1, 2"] {
+        let tok = crate::lexer::parse_tokens(text.as_bytes()).unwrap();
+        assert!(crate::parser::parse_commands(&tok).is_err(), "{}", text);
+    }
 }
@@ -20,6 +20,13 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
         self.limit_accuracy(acc);
     }
 
+    #[inline]
+    fn truncated(&self, acc: usize) -> Self {
+        let mut res = self.clone();
+        res.limit_accuracy(acc);
+        res
+    }
+
     #[inline]
     fn zeroes(acc: usize) -> Self {
         let mut res = Self::zero();
@@ -44,7 +51,9 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
     #[inline]
     fn identity(acc: usize) -> Self {
         let mut res = Self::zeroes(acc);
-        res[1] = Self::Coeff::one();
+        if acc > 1 {
+            res[1] = Self::Coeff::one();
+        }
         res
     }
 
@@ -68,7 +77,7 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn log_derive(&self) -> Self {
-        self.derive() / self
+        self.derive() * &self.recip()
     }
     
     #[inline]
@@ -81,7 +90,7 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
     #[inline]
     fn integrate(&self) -> Self {
         let mut res = self.rshift();
-        for i in 1..self.accuracy() {
+        for i in 1..res.accuracy() {
             res[i] /= Self::Coeff::from(i as u32);
         }
         res
@@ -113,7 +122,7 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
         let mut res = Self::one();
         res.set_accuracy(self.accuracy());
         let mut b = if p < 0 {
-            res.clone() / self
+            self.recip()
         } else {
             self.clone()
         };
@@ -128,55 +137,141 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
         res
     }
 
+    // The q-th root by Newton iteration for g^q = f, g <- g + (f/g^(q-1) - g)/q
     #[inline]
     fn ratpow(&self, p: i32, q: u32) -> Self {
+        // Nothing is known about an empty series
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
         if q == 1 {
             return self.pow(p);
         }
         assert!(self[0].is_one());
-        let mut r = Self::one();
-        r.set_accuracy(self.accuracy());
-        for _i in 0..self.accuracy() {
-            let mut r2 = Self::promote(Self::Coeff::from(q));
-            r2.set_accuracy(self.accuracy());
-            let q = (self.clone() - r.clone() * &r).tail_term() / (r2 * &r).tail_term();
-            if q.is_zero() {
-                return r;
+        let acc = self.accuracy();
+        let inv_q = Self::Coeff::one() / Self::Coeff::from(q);
+        let mut g = Self::promote(Self::Coeff::one());
+        let mut k = 1;
+        while k < acc {
+            k = min(2 * k, acc);
+            g.set_accuracy(k);
+            let step = self.truncated(k) * &g.pow(q as i32 - 1).recip() - &g;
+            for i in 0..k {
+                g[i] += step[i].clone() * &inv_q;
             }
-            r += q;
         }
-        r.pow(p)
+        g.set_accuracy(acc);
+        g.pow(p)
     }
 
-    // Compositional inverse, multiplicative inverse is done through Div
+    // Multiplicative inverse by Newton iteration, g <- g + g(1 - fg) doubles the known terms.
+    // Each step costs a couple of multiplications, so this is as fast as Mul.
+    #[inline]
+    fn recip(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        assert!(!self[0].is_zero());
+        let acc = self.accuracy();
+        let mut g = Self::promote(Self::Coeff::one() / &self[0]);
+        let mut k = 1;
+        while k < acc {
+            k = min(2 * k, acc);
+            g.set_accuracy(k);
+            let mut e = -(self.truncated(k) * &g);
+            e[0] += Self::Coeff::one();
+            g += g.clone() * &e;
+        }
+        g.set_accuracy(acc);
+        g
+    }
+
+    // Compositional inverse by Lagrange inversion, g(n) = [x^(n-1)] h^n / n with h = x/f.
+    // Writing n = a*m + b with m about sqrt(n), each coefficient is a dot product of
+    // h^(a*m) and h^b, so only about 2 sqrt(n) multiplications are needed. Newton
+    // iteration on f(g) = x needs fewer, but over the rationals composing with the
+    // truncated iterates is far slower than these exact powers of h.
+    // Multiplicative inverse is recip.
     #[inline]
     fn inverse(&self) -> Self {
         assert!(self[0].is_zero());
-        let mut sm = Self::zeroes(self.accuracy());
-        let mut res = sm.clone();
-        res[1] = Self::Coeff::one() / &self[1];
-        let mut fpow = self.clone();
-        for i in 0..self.accuracy() {
-            sm[i] += res[1].clone() * &fpow[i];
+        let acc = self.accuracy();
+        let mut res = Self::zeroes(acc);
+        if acc < 2 {
+            return res;
         }
-        for i in 2..self.accuracy() {
-            fpow *= self;
-            res[i] = -sm[i].clone() / &fpow[i];
-            for j in 0..self.accuracy() {
-                sm[j] += res[i].clone() * &fpow[j];
+        let h = self.lshift().recip();
+        let mut m = 1;
+        while m * m < acc {
+            m += 1;
+        }
+        let mut one = Self::one();
+        one.set_accuracy(acc - 1);
+        let mut baby = vec![one.clone()];
+        for b in 1..=m {
+            let next = baby[b - 1].clone() * &h;
+            baby.push(next);
+        }
+        let Some(step) = baby.pop() else { unreachable!() };
+        let mut giant = one;
+        for a in 0..acc.div_ceil(m) {
+            if a > 0 {
+                giant *= &step;
+            }
+            for (b, pow) in baby.iter().enumerate() {
+                let n = a * m + b;
+                if n == 0 {
+                    continue;
+                }
+                if n >= acc {
+                    break;
+                }
+                let mut dot = Self::Coeff::zero();
+                for j in 0..n {
+                    dot += giant[j].clone() * &pow[n - 1 - j];
+                }
+                res[n] = dot / Self::Coeff::from(n as u32);
             }
         }
         res
     }
 
+    // Brent-Kung baby-step giant-step composition. With m about sqrt(n), f splits into
+    // blocks of m terms, each block is a combination of g^0..g^(m-1) and the blocks are
+    // joined by Horner in g^m, so only about 2 sqrt(n) full multiplications are needed.
     #[inline]
     fn compose(&self, other: &Self) -> Self {
         assert!(other[0].is_zero());
         let sig = min(self.accuracy(), other.accuracy());
+        if sig == 0 {
+            return Self::zeroes(0);
+        }
+        let mut m = 1;
+        while m * m < sig {
+            m += 1;
+        }
+        let g = other.truncated(sig);
+        let mut one = Self::one();
+        one.set_accuracy(sig);
+        let mut pows = vec![one];
+        for i in 1..=m {
+            let next = pows[i - 1].clone() * &g;
+            pows.push(next);
+        }
+        let Some(giant) = pows.pop() else { unreachable!() };
         let mut res = Self::zeroes(sig);
-        for i in (0..sig).rev() {
-            res *= other;
-            res[0] += &self[i];
+        for j in (0..sig.div_ceil(m)).rev() {
+            res *= &giant;
+            for (i, pow) in pows.iter().enumerate().take(sig - j * m) {
+                let c = &self[j * m + i];
+                if c.is_zero() {
+                    continue;
+                }
+                // g^i starts at x^i
+                for t in i..sig {
+                    res[t] += c.clone() * &pow[t];
+                }
+            }
         }
         res
     }
@@ -191,23 +286,6 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
         self.lshift().is_zero()
     }
 
-    #[inline]
-    fn tail_term(self) -> Self {
-        let mut found = false;
-        let mut res = self.clone();
-        for i in 0..self.accuracy() {
-            if res[i].is_zero() {
-                continue;
-            }
-            if found {
-                res[i] = Self::Coeff::zero();
-            } else {
-                found = true;
-            }
-        }
-        res
-    }
-
     #[inline]
     fn exp_mul(&self, other: &Self) -> Self {
         let lap1 = self.laplace_inv();
@@ -355,15 +433,41 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
         res
     }
 
+    // exp(a) for a(0) = 0 by Newton iteration, g <- g(1 + a - log g)
     #[inline]
     fn exp(&self) -> Self {
-        let mut res = Self::expx(self.accuracy()).compose(self);
-        res
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        assert!(self[0].is_zero());
+        let acc = self.accuracy();
+        let mut g = Self::promote(Self::Coeff::one());
+        let mut k = 1;
+        while k < acc {
+            k = min(2 * k, acc);
+            g.set_accuracy(k);
+            let mut g1 = g.clone();
+            g1[0] -= Self::Coeff::one();
+            let mut t = self.truncated(k) - &g1.log();
+            t[0] += Self::Coeff::one();
+            g *= &t;
+        }
+        g.set_accuracy(acc);
+        g
     }
 
+    // log(1 + a) for a(0) = 0, the integral of the logarithmic derivative
     #[inline]
     fn log(&self) -> Self {
-        Self::log1px(self.accuracy()).compose(self)
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        assert!(self[0].is_zero());
+        let mut f = self.clone();
+        f[0] += Self::Coeff::one();
+        let mut res = f.log_derive().integrate();
+        res.set_accuracy(self.accuracy());
+        res
     }
     
     #[inline]
@@ -390,6 +494,9 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn bous(&self) -> Self {
+        if self.accuracy() < 2 {
+            return self.clone();
+        }
         let n = self.accuracy();
         let mut one = Self::one();
         one.set_accuracy(self.accuracy());
@@ -399,6 +506,9 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn bous_inv(&self) -> Self {
+        if self.accuracy() < 2 {
+            return self.clone();
+        }
         let n = self.accuracy();
         let mut one = Self::one();
         one.set_accuracy(self.accuracy());
@@ -408,6 +518,10 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn mobius(&self) -> Self {
+        // Only a(0) is known, which the transform drops
+        if self.accuracy() < 2 {
+            return Self::zeroes(self.accuracy());
+        }
         let mut mob = vec![Self::Coeff::zero(); self.accuracy()];
         mob[1] = Self::Coeff::one();
         for i in 1..self.accuracy() {
@@ -438,6 +552,9 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn stirling(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
         let mut stirl = vec![vec![Self::Coeff::zero(); self.accuracy()]; self.accuracy()];
         stirl[0][0] = Self::Coeff::one();
         for i in 1..self.accuracy() {
@@ -457,6 +574,9 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn stirling_inv(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
         let mut stirl = vec![vec![Self::Coeff::zero(); self.accuracy()]; self.accuracy()];
         stirl[0][0] = Self::Coeff::one();
         for i in 1..self.accuracy() {
@@ -476,6 +596,9 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn euler(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
         let da = self.point();
         let c = da.mobius_inv();
         let mut res = Self::zeroes(self.accuracy());
@@ -493,6 +616,9 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn euler_inv(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
         let mut res = Self::zeroes(self.accuracy());
         for i in 1..self.accuracy() {
             res[i] = Self::Coeff::from(i as u32) * &self[i];
@@ -511,6 +637,9 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn lah(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
         let mut fac = vec![];
         let mut mul = Self::Coeff::one();
         fac.push(mul.clone());
@@ -531,6 +660,9 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn lah_inv(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
         let mut fac = vec![];
         let mut mul = Self::Coeff::one();
         fac.push(mul.clone());
@@ -555,10 +687,16 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
 
     #[inline]
     fn powerset(&self) -> Self {
-        let mut res = Self::zero();
-        res.set_accuracy(self.accuracy());
+        self.polya_log().exp()
+    }
+
+    // Sum of (-1)^(k+1) A(x^k)/k over k, the log of the sets of distinct components.
+    // a(0) is ignored.
+    #[inline]
+    fn polya_log(&self) -> Self {
+        let mut res = Self::zeroes(self.accuracy());
         for i in 1..self.accuracy() {
-            for j in 1..self.accuracy()/i+1 {
+            for j in 1..=(self.accuracy() - 1) / i {
                 let coeff = self[i].clone() / Self::Coeff::from(j as u32);
                 if j % 2 == 1 {
                     res[i * j] += coeff;
@@ -567,14 +705,303 @@ pub trait PowerSeries: IndexMut<usize, Output = Self::Coeff> + FromIterator<Self
                 }
             }
         }
-        res.exp()
+        res
+    }
+
+    // A(x^k)
+    #[inline]
+    fn aerate(&self, k: usize) -> Self {
+        let mut res = Self::zeroes(self.accuracy());
+        for i in (0..self.accuracy()).take_while(|i| i * k < self.accuracy()) {
+            res[i * k] = self[i].clone();
+        }
+        res
+    }
+
+    // Constructions of the symbolic method, a counts the components and needs a(0) = 0.
+    // Sequences are the same for labelled and unlabelled classes.
+    #[inline]
+    fn seq(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        let mut res = -self.clone();
+        res[0] = Self::Coeff::one();
+        res.recip()
+    }
+
+    #[inline]
+    fn seq_k(&self, k: usize) -> Self {
+        self.pow(k as i32)
+    }
+
+    // Unlabelled multisets, the Euler transform. It keeps a(0) as the constant term,
+    // which is 1 for the empty multiset.
+    #[inline]
+    fn mset(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        let mut a = self.clone();
+        a[0] = Self::Coeff::one();
+        a.euler()
+    }
+
+    // Unlabelled sets of distinct components
+    #[inline]
+    fn pset(&self) -> Self {
+        self.powerset()
+    }
+
+    // Unlabelled cycles, sum of phi(k)/k log(1/(1 - A(x^k))) over k
+    #[inline]
+    fn cyc(&self) -> Self {
+        let acc = self.accuracy();
+        let l = self.lcyc();
+        let mut res = Self::zeroes(acc);
+        for k in 1..acc {
+            let w = Self::Coeff::from(totient(k) as u32) / Self::Coeff::from(k as u32);
+            for i in (1..acc).take_while(|i| i * k < acc) {
+                res[i * k] += w.clone() * &l[i];
+            }
+        }
+        res
+    }
+
+    // Multisets of exactly k components from the cycle index of the symmetric group,
+    // m_j = (A(x) m_(j-1) + A(x^2) m_(j-2) + ... + A(x^j) m_0) / j
+    #[inline]
+    fn mset_k(&self, k: usize) -> Self {
+        self.polya_k(k, false)
+    }
+
+    #[inline]
+    fn pset_k(&self, k: usize) -> Self {
+        self.polya_k(k, true)
+    }
+
+    // Shared by mset_k and pset_k, for distinct components the signs alternate
+    #[inline]
+    fn polya_k(&self, k: usize, alternate: bool) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        let mut a = self.clone();
+        a[0] = Self::Coeff::zero();
+        let mut m = vec![Self::promote(Self::Coeff::one())];
+        m[0].set_accuracy(self.accuracy());
+        for j in 1..=k {
+            let mut res = Self::zeroes(self.accuracy());
+            for i in 1..=j {
+                let term = a.aerate(i) * &m[j - i];
+                if alternate && i % 2 == 0 {
+                    res -= &term;
+                } else {
+                    res += &term;
+                }
+            }
+            for n in 0..res.accuracy() {
+                res[n] /= Self::Coeff::from(j as u32);
+            }
+            m.push(res);
+        }
+        m.swap_remove(k)
+    }
+
+    // Unlabelled cycles of exactly k components, sum of phi(d) A(x^d)^(k/d) / k over d dividing k
+    #[inline]
+    fn cyc_k(&self, k: usize) -> Self {
+        let mut res = Self::zeroes(self.accuracy());
+        for d in (1..=k).filter(|d| k.is_multiple_of(*d)) {
+            let term = self.aerate(d).pow((k / d) as i32);
+            for n in 0..res.accuracy() {
+                res[n] += Self::Coeff::from(totient(d) as u32) * &term[n];
+            }
+        }
+        for n in 0..res.accuracy() {
+            res[n] /= Self::Coeff::from(k as u32);
+        }
+        res
+    }
+
+    // Labelled sets, exp(A) as EGF
+    #[inline]
+    fn set(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        let mut a = self.clone();
+        a[0] = Self::Coeff::zero();
+        a.exp()
+    }
+
+    // A^k/k!
+    #[inline]
+    fn set_k(&self, k: usize) -> Self {
+        let mut res = self.pow(k as i32);
+        let fact = (1..=k).fold(Self::Coeff::one(), |f, i| f * Self::Coeff::from(i as u32));
+        for n in 0..res.accuracy() {
+            res[n] /= &fact;
+        }
+        res
+    }
+
+    // Labelled cycles, log(1/(1 - A)) as EGF
+    #[inline]
+    fn lcyc(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        let mut a = -self.clone();
+        a[0] = Self::Coeff::zero();
+        -a.log()
+    }
+
+    // A^k/k
+    #[inline]
+    fn lcyc_k(&self, k: usize) -> Self {
+        let mut res = self.pow(k as i32);
+        for n in 0..res.accuracy() {
+            res[n] /= Self::Coeff::from(k as u32);
+        }
+        res
+    }
+
+    // Sum of C(n,k) m^(n-k) a(k), 1/(1-mx) A(x/(1-mx)), the same differences as binomial
+    #[inline]
+    fn binomial_by(&self, m: &Self::Coeff) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        let mut res = Self::zeroes(self.accuracy());
+        let mut summer = self.clone();
+        res[0] = summer[0].clone();
+        for i in 1..self.accuracy() {
+            for j in 0..summer.accuracy()-1 {
+                let nxt = summer[j + 1].clone();
+                summer[j] *= m;
+                summer[j] += nxt;
+            }
+            summer.limit_accuracy(summer.accuracy() - 1);
+            res[i] = summer[0].clone();
+        }
+        res
+    }
+
+    // A(x C(x)) for the Catalan numbers C, x C(x) = (1 - sqrt(1 - 4x))/2
+    #[inline]
+    fn catalan(&self) -> Self {
+        if self.accuracy() == 0 {
+            return Self::zeroes(0);
+        }
+        let acc = self.accuracy();
+        let mut d = Self::zeroes(acc);
+        d[0] = Self::Coeff::one();
+        if acc > 1 {
+            d[1] = -Self::Coeff::from(4u32);
+        }
+        let r = d.ratpow(1, 2);
+        let mut y = Self::zeroes(acc);
+        for i in 1..acc {
+            y[i] = -r[i].clone() / Self::Coeff::from(2u32);
+        }
+        self.compose(&y)
+    }
+
+    // A(x - x^2)
+    #[inline]
+    fn catalan_inv(&self) -> Self {
+        if self.accuracy() < 2 {
+            return self.clone();
+        }
+        let mut y = Self::identity(self.accuracy());
+        if self.accuracy() > 2 {
+            y[2] = -Self::Coeff::one();
+        }
+        self.compose(&y)
+    }
+
+    // a(0), 2a(1), 2a(2), ...
+    #[inline]
+    fn m2(&self) -> Self {
+        let mut res = self.clone();
+        for i in 1..res.accuracy() {
+            res[i] *= Self::Coeff::from(2u32);
+        }
+        res
+    }
+
+    #[inline]
+    fn m2_inv(&self) -> Self {
+        let mut res = self.clone();
+        for i in 1..res.accuracy() {
+            res[i] /= Self::Coeff::from(2u32);
+        }
+        res
+    }
+
+    // a(0), -a(1), -a(2), ...
+    #[inline]
+    fn negate(&self) -> Self {
+        let mut res = -self.clone();
+        if res.accuracy() > 0 {
+            res[0] = self[0].clone();
+        }
+        res
+    }
+
+    // Sum of (-1)^(n-k) a(k), A/(1+x)
+    #[inline]
+    fn partial_sums_signed(&self) -> Self {
+        let mut res = Self::zeroes(self.accuracy());
+        let mut sm = Self::Coeff::zero();
+        for i in 0..res.accuracy() {
+            sm = self[i].clone() - sm;
+            res[i] = sm.clone();
+        }
+        res
+    }
+
+    // a(mn + r), bisections and trisections
+    #[inline]
+    fn section(&self, m: usize, r: usize) -> Self {
+        (r..self.accuracy()).step_by(m).map(|i| self[i].clone()).collect()
     }
+
+    // x B(x) is the compositional inverse of x A(x), like the OEIS REVERT with offset 1
+    #[inline]
+    fn revert(&self) -> Self {
+        self.rshift().inverse().lshift()
+    }
+
+    // As revert, for exponential generating functions
+    #[inline]
+    fn revert_egf(&self) -> Self {
+        self.rshift().laplace_inv().inverse().laplace().lshift()
+    }
+}
+
+fn totient(n: usize) -> usize {
+    let mut res = n;
+    let mut m = n;
+    let mut p = 2;
+    while p * p <= m {
+        if m.is_multiple_of(p) {
+            while m.is_multiple_of(p) { m /= p; }
+            res -= res / p;
+        }
+        p += 1;
+    }
+    if m > 1 { res -= res / m; }
+    res
 }
 
 #[cfg(test)]
 mod tests {
     use crate::PowerSeries;
     use crate::Series;
+    use crate::mathtypes::One;
+    use rug::Rational;
 
     #[test]
     fn test_mobius() {
@@ -606,6 +1033,113 @@ mod tests {
         assert_eq!(inp, inp.lah_inv().lah());
     }
 
+    #[test]
+    fn test_newton() {
+        use crate::{ShortSeq, ModIntP32};
+        let catalan: Series = "1,1,2,5,14,42,132,429,1430,4862,16796,58786,208012,742900,2674440,9694845".parse().unwrap();
+        let a = catalan.rshift().truncated(16);
+        assert_eq!(a.exp(), Series::expx(16).compose(&a));
+        assert_eq!(a.log(), Series::log1px(16).compose(&a));
+        let mut e = a.exp();
+        e[0] -= Rational::one();
+        assert_eq!(e.log(), a);
+        let mut one = Series::one();
+        one.set_accuracy(16);
+        assert_eq!(catalan.recip(), one / &catalan);
+        assert_eq!(catalan.ratpow(-2, 3).pow(3), catalan.pow(-2));
+        assert_eq!(catalan.pow(2).sqrt(), catalan);
+        assert_eq!(a.integrate().accuracy(), 17);
+        assert_eq!(a.integrate()[16], Rational::from((2674440, 16)));
+        let short: ShortSeq<ModIntP32> = "0,1,2,5,14,42,132,429,1430,4862,16796,58786,208012,742900,2674440,9694845".parse().unwrap();
+        assert_eq!(short.exp(), ShortSeq::<ModIntP32>::expx(16).compose(&short));
+        assert_eq!(short.log(), ShortSeq::<ModIntP32>::log1px(16).compose(&short));
+        assert_eq!(short.exp().accuracy(), 16);
+        let empty = Series::zeroes(0);
+        for res in [empty.exp(), empty.log(), empty.recip(), empty.ratpow(1, 2), empty.ratpow(-1, 1), empty.exp_integ()] {
+            assert_eq!(res.accuracy(), 0);
+        }
+    }
+
+    #[test]
+    fn test_reversion() {
+        use crate::{ShortSeq, ModIntP32};
+        // x - x^2 reverts to x times the Catalan numbers
+        let f: Series = "0,1,-1,0,0,0,0,0,0,0,0,0,0,0,0,0".parse().unwrap();
+        let catalan: Series = "0,1,1,2,5,14,42,132,429,1430,4862,16796,58786,208012,742900,2674440".parse().unwrap();
+        assert_eq!(f.inverse(), catalan);
+        assert_eq!(catalan.compose(&f), Series::identity(16));
+        // Compare with plain Horner
+        let g: Series = "0,2,-1,3,1/2,0,7,1,1,-4,0,0,2,1,1,9,3".parse().unwrap();
+        let h: Series = "1,1,2,3,5,8,13,21,34,55,89,144,233,377,610,987,1597".parse().unwrap();
+        let mut horner = Series::zeroes(17);
+        for i in (0..17).rev() {
+            horner *= &g;
+            horner[0] += &h[i];
+        }
+        assert_eq!(h.compose(&g), horner);
+        assert_eq!(g.inverse().compose(&g), Series::identity(17));
+        assert_eq!(g.compose(&g.inverse()), Series::identity(17));
+        let short: ShortSeq<ModIntP32> = "0,2,65520,3,5,0,7,1,1,4,0,0,2,1,1,9".parse().unwrap();
+        assert_eq!(short.inverse().compose(&short), ShortSeq::<ModIntP32>::identity(16));
+    }
+
+    #[test]
+    fn test_constructions() {
+        let pos: Series = "0,1,1,1,1,1,1,1,1,1,1,1".parse().unwrap();
+        assert_eq!(pos.seq(), "1,1,2,4,8,16,32,64,128,256,512,1024".parse().unwrap());
+        assert_eq!(pos.mset(), "1,1,2,3,5,7,11,15,22,30,42,56".parse().unwrap());
+        assert_eq!(pos.pset(), "1,1,1,2,2,3,4,5,6,8,10,12".parse().unwrap());
+        assert_eq!(pos.cyc(), "0,1,2,3,5,7,13,19,35,59,107,187".parse().unwrap());
+        assert_eq!(pos.mset_k(2), "0,0,1,1,2,2,3,3,4,4,5,5".parse().unwrap());
+        assert_eq!(pos.pset_k(2), "0,0,0,1,1,2,2,3,3,4,4,5".parse().unwrap());
+        assert_eq!(pos.seq_k(2), "0,0,1,2,3,4,5,6,7,8,9,10".parse().unwrap());
+        let mut sum = Series::zeroes(12);
+        for k in 0..12 {
+            sum += &pos.mset_k(k);
+        }
+        assert_eq!(sum, pos.mset());
+        // Binary necklaces
+        let two: Series = "0,2,0,0,0,0,0,0,0,0,0,0".parse().unwrap();
+        assert_eq!(two.cyc(), "0,2,3,4,6,8,14,20,36,60,108,188".parse().unwrap());
+        assert_eq!(two.cyc_k(6)[6], Rational::from(14));
+        // Labelled, as EGFs
+        let x = Series::identity(8);
+        assert_eq!(x.set().laplace(), "1,1,1,1,1,1,1,1".parse().unwrap());
+        assert_eq!(x.lcyc().laplace(), "0,1,1,2,6,24,120,720".parse().unwrap());
+        assert_eq!(x.lcyc_k(3).laplace(), "0,0,0,2,0,0,0,0".parse().unwrap());
+        assert_eq!(x.set().set().laplace(), "1,1,2,5,15,52,203,877".parse().unwrap());
+        let mut nonempty = x.set();
+        nonempty[0] = Rational::from(0);
+        assert_eq!(nonempty.set_k(2).laplace(), "0,0,1,3,7,15,31,63".parse().unwrap());
+        // Nothing is known about an empty series, and nothing about its constructions
+        let empty = Series::zeroes(0);
+        for f in [Series::seq, Series::mset, Series::pset, Series::cyc, Series::set, Series::lcyc] {
+            assert_eq!(f(&empty).accuracy(), 0);
+        }
+        for f in [Series::seq_k, Series::mset_k, Series::pset_k, Series::cyc_k, Series::set_k, Series::lcyc_k] {
+            assert_eq!(f(&empty, 2).accuracy(), 0);
+        }
+    }
+
+    #[test]
+    fn test_hops() {
+        let ones: Series = "1,1,1,1,1,1,1,1".parse().unwrap();
+        let catalan: Series = "1,1,2,5,14,42,132,429".parse().unwrap();
+        assert_eq!(ones.binomial_by(&Rational::from(2)), "1,3,9,27,81,243,729,2187".parse().unwrap());
+        assert_eq!(ones.binomial_by(&Rational::from(1)), ones.binomial());
+        assert_eq!(catalan.binomial_by(&Rational::from(-1)), catalan.binomial_inv());
+        assert_eq!(ones.catalan(), catalan);
+        assert_eq!(catalan.catalan_inv(), ones);
+        assert_eq!(ones.m2().m2_inv(), ones);
+        assert_eq!(ones.negate(), "1,-1,-1,-1,-1,-1,-1,-1".parse().unwrap());
+        assert_eq!(ones.partial_sums_signed(), "1,0,1,0,1,0,1,0".parse().unwrap());
+        assert_eq!(catalan.section(2, 1), "1,5,42,429".parse().unwrap());
+        assert_eq!(catalan.section(3, 0), "1,5,132".parse().unwrap());
+        assert_eq!(ones.revert(), "1,-1,1,-1,1,-1,1,-1".parse().unwrap());
+        // e^x - 1 reverts to log(1 + x)
+        assert_eq!(ones.revert_egf(), "1,-1,2,-6,24,-120,720,-5040".parse().unwrap());
+    }
+
     #[test]
     fn test_euler() {
         let conn: Series = "1,1,1,2,6,20,99,646,5974,71885,1052805,17449299,313372298".parse().unwrap();
@@ -1,28 +1,129 @@
-use crate::{lexer, parser, Series};
-use crate::lexer::Operator;
-use crate::parser::SyntaxNode;
+use crate::{lexer, parser, help, arith, hops, Series, SeriesFormat, Laurent};
+use crate::lexer::{Operator, ParseError};
+use crate::error::PerqError;
+use crate::parser::{SyntaxNode, NodeKind};
 use rustc_hash::FxHashMap;
-use crate::oeis::SeqDB;
+use crate::oeis::{SeqDB, SearchResult, TopResults};
 use crate::powerseries::PowerSeries;
 use crate::mathtypes::*;
-use rug::Rational;
+use rug::{Integer, Rational};
 use std::str::FromStr;
+use std::cmp::min;
 
 pub struct RunTimeEnvironment {
     constant_variables: FxHashMap<String,Rational>,
     series_variables: FxHashMap<String,Series>,
+    laurent_variables: FxHashMap<String,Laurent>,
+    search_variables: FxHashMap<String,TopResults>,
     search_database: SeqDB,
     default_precision: usize,
+    // Print name = value after let and letrec
+    echo_assignments: bool,
+    // How printed series look, set with set_format and set_order
+    format: SeriesFormat,
+    show_order: bool,
+    functions: FxHashMap<String,UserFunction>,
+    call_depth: usize,
 }
 
-#[derive(Debug)]
+// Defined with def, parameters are bound in a local scope when called
+#[derive(Debug, Clone)]
+struct UserFunction {
+    params: Vec<String>,
+    body: SyntaxNode,
+}
+
+const MAX_CALL_DEPTH: usize = 64;
+
+#[derive(Debug, Clone)]
 pub enum ExprValue {
     SeriesExpr(Series),
-    ConstExpr(Rational)
+    // Only for negative or fractional exponents, anything else is a SeriesExpr
+    LaurentExpr(Laurent),
+    ConstExpr(Rational),
+    TextExpr(String),
+    SearchExpr(TopResults)
 }
 
 impl ExprValue {
-    fn apply_binop(x: ExprValue, op: Operator, y: ExprValue) -> Result<ExprValue, String> {
+    // Exponent p/q of a power
+    fn exponent(c: &Rational) -> Result<(i32, u32), PerqError> {
+        let Ok(n) = i32::try_from(c.numer()) else {
+            return Err(PerqError::Domain("Exponent does not fit in i32".to_string()));
+        };
+        let Ok(m) = u32::try_from(c.denom()) else {
+            return Err(PerqError::Domain("Exponent does not fit in u32".to_string()));
+        };
+        Ok((n, m))
+    }
+
+    fn from_laurent(l: Laurent) -> ExprValue {
+        match l.to_series() {
+            Some(s) => SeriesExpr(s),
+            None => LaurentExpr(l)
+        }
+    }
+
+    fn laurent_value(x: ExprValue) -> Result<Laurent, PerqError> {
+        match x {
+            SeriesExpr(s) => Ok(Laurent::from(s)),
+            LaurentExpr(l) => Ok(l),
+            _ => Err(PerqError::InvalidArgument("Operator only applies to series and constants.".to_string()))
+        }
+    }
+
+    // Division by a series with zero constant, powers with a leading x^k or a
+    // leading coefficient other than one, and anything with a Laurent operand
+    fn needs_laurent(x: &ExprValue, op: Operator, y: &ExprValue) -> bool {
+        if !matches!(op, Operator::Add | Operator::Sub | Operator::Mul | Operator::Div | Operator::Pow) {
+            return false;
+        }
+        match (x, op, y) {
+            (LaurentExpr(_), _, _) | (_, _, LaurentExpr(_)) => true,
+            (_, Operator::Div, SeriesExpr(s)) => s.seq.first().is_some_and(|c| c.is_zero()),
+            (SeriesExpr(s), Operator::Pow, ConstExpr(c)) => s.seq.first().is_some_and(|a| {
+                (!c.denom().is_one() && !a.is_one()) || (*c < 0 && a.is_zero())
+            }),
+            _ => false
+        }
+    }
+
+    fn apply_laurent(x: ExprValue, op: Operator, y: ExprValue) -> Result<ExprValue, PerqError> {
+        if op == Operator::Pow {
+            let ConstExpr(c) = y else {
+                return Err(PerqError::InvalidArgument("Can not raise to power of series.".to_string()));
+            };
+            let (n, m) = Self::exponent(&c)?;
+            return Ok(Self::from_laurent(Self::laurent_value(x)?.ratpow(n, m)?));
+        }
+        let (a, b) = match (x, y) {
+            (ConstExpr(c), y) => {
+                let b = Self::laurent_value(y)?;
+                (Laurent::constant(c, &b), b)
+            },
+            (x, ConstExpr(d)) => {
+                let a = Self::laurent_value(x)?;
+                let b = Laurent::constant(d, &a);
+                (a, b)
+            },
+            (x, y) => (Self::laurent_value(x)?, Self::laurent_value(y)?)
+        };
+        let res = match op {
+            Operator::Add => a.checked_add(&b)?,
+            Operator::Sub => a.checked_sub(&b)?,
+            Operator::Mul => a.checked_mul(&b)?,
+            _ => a.checked_mul(&b.recip()?)?
+        };
+        Ok(Self::from_laurent(res))
+    }
+
+    fn apply_binop(x: ExprValue, op: Operator, y: ExprValue) -> Result<ExprValue, PerqError> {
+        if Self::needs_laurent(&x, op, &y) {
+            return Self::apply_laurent(x, op, y);
+        }
+        if matches!(x, LaurentExpr(_)) || matches!(y, LaurentExpr(_)) {
+            return Err(PerqError::InvalidArgument(format!("{} is not supported for Laurent series.", op)));
+        }
         match op {
             Operator::Add => {
                 use std::ops::Add;
@@ -37,96 +138,92 @@ impl ExprValue {
                 binop_promotion!(x, Mul::mul, y)
             },
             Operator::Div => {
-                match y {
-                    SeriesExpr(ref s) => {
-                        if s.is_zero() {
-                            return Err("Division by zero.".to_string());
-                        }
-                    },
-                    ConstExpr(ref c) => {
-                        if c.is_zero() {
-                            return Err("Division by zero.".to_string());
-                        }
-                    }
+                let zero_div = match y {
+                    SeriesExpr(ref s) => s.is_zero(),
+                    ConstExpr(ref c) => c.is_zero(),
+                    _ => false
+                };
+                if zero_div {
+                    return Err(PerqError::DivisionByZero);
                 }
                 use std::ops::Div;
                 binop_promotion!(x, Div::div, y)
             },
             Operator::Pow => {
                 let ConstExpr(c) = y else {
-                    return Err("Can not raise to power of series.".to_string());
-                };
-                let Ok(n) = i32::try_from(c.numer()) else {
-                    return Err("Exponent does not fit in i32".to_string());
-                };
-                let Ok(m) = u32::try_from(c.denom()) else {
-                    return Err("Exponent does not fit in u32".to_string());
+                    return Err(PerqError::InvalidArgument("Can not raise to power of series.".to_string()));
                 };
+                if let ConstExpr(d) = x {
+                    return Ok(ConstExpr(arith::pow(&d, &c)?));
+                }
+                let (n, m) = Self::exponent(&c)?;
                 match x {
-                    ConstExpr(d) => {
-                        if m != 1 {
-                            return Err("Roots of rationals not possible".to_string())
-                        }
-                        use rug::ops::Pow;
-                        Ok(ConstExpr(d.pow(n)))
-                    },
-                    SeriesExpr(s) => {
-                        if m != 1 && !s[0].is_one() {
-                            return Err("Roots of series with no constant not possible.".to_string());
-                        }
-                        Ok(SeriesExpr(s.ratpow(n, m)))
-                    }
+                    SeriesExpr(s) => Ok(SeriesExpr(s.ratpow(n, m))),
+                    _ => Err(PerqError::InvalidArgument("Can only raise series and constants to a power.".to_string()))
                 }
             },
             Operator::Compose => {
                 let SeriesExpr(xseq) = x else {
-                    return Err("Can not compose with constant.".to_string());
+                    return Err(PerqError::InvalidArgument("Can not compose with constant.".to_string()));
                 };
                 let SeriesExpr(yseq) = y else {
-                    return Err("Can not compose with constant.".to_string());
+                    return Err(PerqError::InvalidArgument("Can not compose with constant.".to_string()));
                 };
-                if !yseq[0].is_zero() {
-                    return Err("Can not compose with series with non-zero constant.".to_string());
+                match yseq.seq.first() {
+                    None => { return Err(PerqError::Domain("Can not compose with series with unknown constant.".to_string())); },
+                    Some(c) if !c.is_zero() => { return Err(PerqError::Domain("Can not compose with series with non-zero constant.".to_string())); },
+                    _ => { }
                 }
                 Ok(SeriesExpr(xseq.compose(&yseq)))
             },
             Operator::PointMul => {
                 let SeriesExpr(s) = x else {
-                    return Err("Can not do point multiplication on constant.".to_string());
+                    return Err(PerqError::InvalidArgument("Can not do point multiplication on constant.".to_string()));
                 };
                 let SeriesExpr(t) = y else {
-                    return Err("Can not do point multiplication on constant.".to_string());
+                    return Err(PerqError::InvalidArgument("Can not do point multiplication on constant.".to_string()));
                 };
                 Ok(SeriesExpr(s.hadamard(&t)))
             }
             Operator::PointDiv => {
                 let SeriesExpr(s) = x else {
-                    return Err("Can not do point division on constant.".to_string());
+                    return Err(PerqError::InvalidArgument("Can not do point division on constant.".to_string()));
                 };
                 let SeriesExpr(t) = y else {
-                    return Err("Can not do point division on constant.".to_string());
+                    return Err(PerqError::InvalidArgument("Can not do point division on constant.".to_string()));
                 };
                 for coeff in &t.seq {
                     if coeff.is_zero() {
-                        return Err("Division by zero.".to_string())
+                        return Err(PerqError::DivisionByZero)
                     }
                 }
                 Ok(SeriesExpr(s.point_div(&t)))
             }
             Operator::DefineEqual => {
-                Err("Invalid use of define equals.".to_string())
+                Err(PerqError::InvalidArgument("Invalid use of define equals.".to_string()))
+            }
+            Operator::Factorial => {
+                Err(PerqError::InvalidArgument("Factorial takes one argument.".to_string()))
             }
         }
     }
-    fn apply_unop(op: Operator, x: ExprValue) -> Result<ExprValue, String> {
+    fn apply_unop(op: Operator, x: ExprValue) -> Result<ExprValue, PerqError> {
         match op {
             Operator::Sub => {
-                Ok(match x {
-                    SeriesExpr(s) => SeriesExpr(-s),
-                    ConstExpr(s) => ConstExpr(-s)
-                })
+                match x {
+                    SeriesExpr(s) => Ok(SeriesExpr(-s)),
+                    LaurentExpr(l) => Ok(LaurentExpr(-&l)),
+                    ConstExpr(s) => Ok(ConstExpr(-s)),
+                    _ => Err(PerqError::InvalidArgument("Can only negate series and constants.".to_string()))
+                }
+            },
+            Operator::Factorial => {
+                let ConstExpr(n) = x else {
+                    return Err(PerqError::InvalidArgument("Factorial takes a constant.".to_string()));
+                };
+                Ok(ConstExpr(Rational::from(arith::factorial(&n)?)))
             },
-            _ => Err("Unknown unary operator.".to_string())
+            _ => Err(PerqError::InvalidArgument("Unknown unary operator.".to_string()))
         }
     }
 }
@@ -135,60 +232,373 @@ impl std::fmt::Display for ExprValue {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         match self {
             SeriesExpr(s) => write!(f, "{}", s),
-            ConstExpr(s) => write!(f, "{}", s)
+            LaurentExpr(l) => write!(f, "{}", l),
+            ConstExpr(s) => write!(f, "{}", s),
+            TextExpr(s) => write!(f, "{}", s),
+            SearchExpr(s) => write!(f, "{}", s)
         }
     }
 }
 
 use crate::runtime::ExprValue::*;
 
-// TODO: If end with semicolon do not print
-// TODO: Make default precision settable
+// Unbalanced brackets, a trailing operator or comma, or an open block comment
+pub fn is_incomplete(text: &str) -> bool {
+    lexer::is_incomplete(text.as_bytes())
+}
+
+// Literals are integers or decimals with an optional exponent like 2.5e-3, all exact
+fn parse_literal(s: &str) -> Option<Rational> {
+    let (mantissa, exp) = match s.find(['e', 'E']) {
+        Some(i) => (&s[..i], s[i+1..].parse::<i32>().ok()?),
+        None => (s, 0)
+    };
+    let (int, frac) = mantissa.split_once('.').unwrap_or((mantissa, ""));
+    let digits = Integer::from_str(&format!("{}{}", int, frac)).ok()?;
+    let exp = exp.checked_sub(i32::try_from(frac.len()).ok()?)?;
+    use rug::ops::Pow;
+    Some(Rational::from(digits) * Rational::from(10).pow(exp))
+}
 
 impl RunTimeEnvironment {
-    pub fn new(filename: String) -> std::io::Result<Self> {
+    pub fn new(filename: String) -> Result<Self, PerqError> {
         Ok(Self {
             constant_variables: Default::default(),
             series_variables: Default::default(),
+            laurent_variables: Default::default(),
+            search_variables: Default::default(),
             search_database: SeqDB::from_stripped(filename)?,
             default_precision: 16,
+            echo_assignments: false,
+            format: SeriesFormat::Ogf,
+            show_order: false,
+            functions: Default::default(),
+            call_depth: 0,
         })
     }
 
     fn transformation_lookup(name: &str) -> Option<fn(&Series) -> Series> {
-        function_lookups!( name, point, sqrt, derive, integrate, log_derive, exp_integ, inverse, delta, partial_sums, partial_products, t019, laplace, laplace_inv, bous, bous_inv, mobius, mobius_inv, stirling, stirling_inv, euler, euler_inv, lah, lah_inv, powerset, lshift, rshift )
+        match name {
+            // The trait method is log(1+a)
+            "log" => Some(|s: &Series| {
+                let mut t = s.clone();
+                t[0] = Rational::new();
+                t.log()
+            }),
+            _ => function_lookups!( name, point, sqrt, derive, integrate, log_derive, exp_integ, inverse, exp, delta, partial_sums, partial_products, t019, laplace, laplace_inv, bous, bous_inv, mobius, mobius_inv, stirling, stirling_inv, euler, euler_inv, lah, lah_inv, powerset, lshift, rshift )
+        }
+    }
+
+    // Transforms that need a particular constant term, the rest accept anything.
+    // Terms that are not known fail the condition.
+    fn check_domain(name: &str, s: &Series) -> Result<(), PerqError> {
+        let a0 = s.seq.first();
+        let a1 = s.seq.get(1);
+        let ok = match name {
+            "log" => a0.is_some_and(|c| c.is_one()),
+            "log_derive" => a0.is_some_and(|c| !c.is_zero()),
+            "exp" | "seq" | "mset" | "pset" | "cyc" | "set" | "lcyc" => a0.is_some_and(|c| c.is_zero()),
+            "seq_k" | "mset_k" | "pset_k" | "cyc_k" | "set_k" | "lcyc_k" => a0.is_some_and(|c| c.is_zero()),
+            "inverse" => a0.is_some_and(|c| c.is_zero()) && a1.is_some_and(|c| !c.is_zero()),
+            _ => true
+        };
+        if ok {
+            return Ok(());
+        }
+        let condition = help::lookup(name).and_then(|e| e.condition).unwrap_or("invalid argument");
+        Err(PerqError::Domain(format!("{} {}.", name, condition)))
     }
 
     fn convolution_lookup(name: &str) -> Option<fn(&Series, &Series) -> Series> {
         function_lookups!( name, hadamard, exp_mul, dirichlet )
     }
 
+    fn construction_lookup(name: &str) -> Option<fn(&Series) -> Series> {
+        function_lookups!( name, seq, mset, pset, cyc, set, lcyc )
+    }
+
+    // Constructions with exactly k components
+    fn sized_construction_lookup(name: &str) -> Option<fn(&Series, usize) -> Series> {
+        function_lookups!( name, seq_k, mset_k, pset_k, cyc_k, set_k, lcyc_k )
+    }
+
+    fn construct(&self, name: &str, vals: Vec<ExprValue>) -> Result<ExprValue, PerqError> {
+        let usage = help::lookup(name).map_or(name, |e| e.usage);
+        let a = match vals.first() {
+            Some(v) => self.series_value(v.clone())?,
+            None => { return Err(PerqError::InvalidArgument(format!("Expected {}.", usage))); }
+        };
+        Self::check_domain(name, &a)?;
+        match (Self::construction_lookup(name), Self::sized_construction_lookup(name), &vals[1..]) {
+            (Some(f), _, []) => Ok(SeriesExpr(f(&a))),
+            (_, Some(f), [ConstExpr(k)]) => {
+                let k = Self::as_index(k)?;
+                if k == 0 && name.ends_with("cyc_k") {
+                    return Err(PerqError::Domain(format!("{} needs at least one component.", name)));
+                }
+                Ok(SeriesExpr(f(&a, k)))
+            },
+            _ => Err(PerqError::InvalidArgument(format!("Expected {}.", usage)))
+        }
+    }
+
+    // HOPS transforms take a series and optionally constant parameters
+    fn hops(&self, name: &str, f: hops::HopsFn, vals: Vec<ExprValue>) -> Result<ExprValue, PerqError> {
+        let entry = help::lookup(name);
+        let usage = entry.map_or(name, |e| e.usage);
+        let arity = entry.map_or(1, |e| e.arity);
+        let mut vals = vals.into_iter();
+        let a = match vals.next() {
+            Some(v) if vals.len() < arity => self.series_value(v)?,
+            _ => { return Err(PerqError::InvalidArgument(format!("Expected {}.", usage))); }
+        };
+        let Some(params) = vals.map(|v| match v {
+            ConstExpr(c) => Some(c),
+            _ => None
+        }).collect::<Option<Vec<Rational>>>() else {
+            return Err(PerqError::InvalidArgument(format!("{} takes constant parameters.", name)));
+        };
+        Ok(SeriesExpr(f(&a, &params)?))
+    }
+
     fn series_lookup(&self, name: &str) -> Option<Series> {
+        if name == "x" {
+            return Some(Series::identity(self.default_precision));
+        }
         series_lookups!( name, self, cos, sin, tan, expx, log1px )
     }
 
-    fn evaluate(&mut self, node: &SyntaxNode) -> Result<ExprValue,String> {
-        match node {
-            SyntaxNode::Constant(s) => Ok(ExprValue::ConstExpr(
-                    match Rational::from_str(
-                        match std::str::from_utf8(s) {
-                            Ok(s) => s,
-                            Err(msg) => { return Err(msg.to_string()); }
-                        }) {
-                        Ok(r) => r,
-                        Err(msg) => { return Err(msg.to_string()); }
+    fn help(args: &[SyntaxNode]) -> Result<ExprValue, PerqError> {
+        match args {
+            [] => Ok(TextExpr(help::overview())),
+            [SyntaxNode { kind: NodeKind::Identifier(name), .. }] => {
+                let Ok(key) = std::str::from_utf8(name) else {
+                    return Err(PerqError::InvalidArgument("Invalid UTF8.".to_string()));
+                };
+                match help::lookup(key) {
+                    Some(entry) => Ok(TextExpr(entry.to_string())),
+                    None => Err(PerqError::InvalidArgument(format!("No help found for {}.", key)))
+                }
+            },
+            _ => Err(PerqError::InvalidArgument("Help takes no argument or a single name.".to_string()))
+        }
+    }
+
+    // Removes a variable, returning its value
+    fn unbind(&mut self, key: &str) -> Option<ExprValue> {
+        let series = self.series_variables.remove(key).map(SeriesExpr);
+        let laurent = self.laurent_variables.remove(key).map(LaurentExpr);
+        let constant = self.constant_variables.remove(key).map(ConstExpr);
+        let search = self.search_variables.remove(key).map(SearchExpr);
+        series.or(laurent).or(constant).or(search)
+    }
+
+    fn bind(&mut self, key: &str, val: ExprValue) -> Result<(), PerqError> {
+        if let TextExpr(_) = val {
+            return Err(PerqError::InvalidArgument("Can not bind text to a variable.".to_string()));
+        }
+        if key == "x" {
+            return Err(PerqError::InvalidArgument("x is reserved for the series variable.".to_string()));
+        }
+        self.unbind(key);
+        self.functions.remove(key);
+        match val {
+            SeriesExpr(s) => { self.series_variables.insert(key.to_string(), s); },
+            LaurentExpr(l) => { self.laurent_variables.insert(key.to_string(), l); },
+            ConstExpr(c) => { self.constant_variables.insert(key.to_string(), c); },
+            SearchExpr(r) => { self.search_variables.insert(key.to_string(), r); },
+            TextExpr(_) => unreachable!()
+        }
+        Ok(())
+    }
+
+    fn as_index(i: &Rational) -> Result<usize, PerqError> {
+        let Ok(ind) = usize::try_from(i.numer()) else {
+            return Err(PerqError::InvalidArgument("Index must be a non-negative integer.".to_string()));
+        };
+        if !i.denom().is_one() {
+            return Err(PerqError::InvalidArgument("Index must be a non-negative integer.".to_string()));
+        }
+        Ok(ind)
+    }
+
+    fn search_entry(res: &TopResults, ind: usize) -> Result<&SearchResult, PerqError> {
+        match res.get(ind) {
+            Some(entry) => Ok(entry),
+            None => Err(PerqError::InvalidArgument(format!("Index out of range, search has {} results.", res.len())))
+        }
+    }
+
+    // Coefficient n of a series, or the series of search result n
+    fn index(val: &ExprValue, i: &Rational) -> Result<ExprValue, PerqError> {
+        let ind = Self::as_index(i)?;
+        match val {
+            SeriesExpr(s) => match s.seq.get(ind) {
+                Some(c) => Ok(ConstExpr(c.clone())),
+                None => Err(PerqError::Domain(format!("Only {} terms of the series are known.", s.accuracy())))
+            },
+            SearchExpr(res) => Ok(SeriesExpr(Self::search_entry(res, ind)?.series.clone())),
+            _ => Err(PerqError::InvalidArgument("Only series and search results can be indexed.".to_string()))
+        }
+    }
+
+    fn evaluate_index(&mut self, node: &SyntaxNode) -> Result<usize, PerqError> {
+        let ConstExpr(i) = self.evaluate(node)? else {
+            return Err(PerqError::InvalidArgument("Expected constant index.".to_string()));
+        };
+        Self::as_index(&i)
+    }
+
+    // Constants are exact, so they are known to any precision
+    fn series_value(&self, val: ExprValue) -> Result<Series, PerqError> {
+        match val {
+            SeriesExpr(s) => Ok(s),
+            ConstExpr(c) => {
+                let mut s = Series::promote(c);
+                s.expand_to(self.default_precision);
+                Ok(s)
+            },
+            _ => Err(PerqError::InvalidArgument("Expected series or constant.".to_string()))
+        }
+    }
+
+    // Fixed point iteration from zero, each round fixes at least one more coefficient
+    // as long as coefficient n of the right hand side only depends on lower ones.
+    // So round i only needs i+1 terms, at full precision it stops when the series repeats.
+    fn solve_recursive(&mut self, key: &str, expr: &SyntaxNode) -> Result<Series, PerqError> {
+        let prec = self.default_precision;
+        let old_val = self.series_variables.insert(key.to_string(), Series::zeroes(0));
+        let mut res = Err(PerqError::Domain("Recursive definition did not converge.".to_string()));
+        for i in 0..prec+2 {
+            let len = min(i + 1, prec);
+            let cur = self.series_variables.get_mut(key).unwrap();
+            cur.set_accuracy(len);
+            self.default_precision = len;
+            let val = self.evaluate(expr);
+            self.default_precision = prec;
+            let nxt = match val {
+                Ok(SeriesExpr(mut s)) => {
+                    if s.accuracy() < len {
+                        res = Err(PerqError::Domain("Recursive definition lost accuracy.".to_string()));
+                        break;
                     }
-            )),
-            SyntaxNode::Identifier(s) => {
+                    s.limit_accuracy(len);
+                    s
+                },
+                Ok(c) => match self.series_value(c) {
+                    Ok(mut s) => {
+                        s.set_accuracy(len);
+                        s
+                    },
+                    Err(msg) => {
+                        res = Err(msg);
+                        break;
+                    }
+                },
+                Err(msg) => {
+                    res = Err(msg);
+                    break;
+                }
+            };
+            if len == prec && self.series_variables[key] == nxt {
+                res = Ok(nxt);
+                break;
+            }
+            self.series_variables.insert(key.to_string(), nxt);
+        }
+        match old_val {
+            Some(v) => { self.series_variables.insert(key.to_string(), v); },
+            None => { self.series_variables.remove(key); }
+        }
+        res
+    }
+
+    fn define(&mut self, name: &str, params: &[Vec<u8>], body: &SyntaxNode) -> Result<ExprValue, PerqError> {
+        if name == "x" || help::lookup(name).is_some_and(|e| e.kind != help::EntryKind::Operator) || Self::transformation_lookup(name).is_some() {
+            return Err(PerqError::InvalidArgument(format!("Can not redefine built-in {}.", name)));
+        }
+        let mut param_names: Vec<String> = vec![];
+        for p in params {
+            let Ok(p) = std::str::from_utf8(p) else {
+                return Err(PerqError::InvalidArgument("Failed to parse parameter name".to_string()));
+            };
+            if p == "x" {
+                return Err(PerqError::InvalidArgument("x is reserved for the series variable.".to_string()));
+            }
+            if param_names.iter().any(|q| q == p) {
+                return Err(PerqError::InvalidArgument(format!("Parameter {} appears twice.", p)));
+            }
+            param_names.push(p.to_string());
+        }
+        let signature = format!("{}({})", name, param_names.join(", "));
+        self.unbind(name);
+        self.functions.insert(name.to_string(), UserFunction { params: param_names, body: body.clone() });
+        Ok(TextExpr(signature))
+    }
+
+    // Parameters shadow variables of the same name until the call returns
+    fn call(&mut self, name: &str, func: UserFunction, vals: Vec<ExprValue>) -> Result<ExprValue, PerqError> {
+        if func.params.len() != vals.len() {
+            return Err(PerqError::InvalidArgument(format!("{} takes {} argument(s).", name, func.params.len())));
+        }
+        if self.call_depth >= MAX_CALL_DEPTH {
+            return Err(PerqError::Domain(format!("Calls nested deeper than {}.", MAX_CALL_DEPTH)));
+        }
+        // Parameters hide variables and user functions of the same name until the body is done
+        let shadowed: Vec<Option<ExprValue>> = func.params.iter().map(|p| self.unbind(p)).collect();
+        let shadowed_functions: Vec<Option<UserFunction>> = func.params.iter().map(|p| self.functions.remove(p)).collect();
+        let mut res = Ok(ConstExpr(Default::default()));
+        for (p, v) in std::iter::zip(&func.params, vals) {
+            if let Err(err) = self.bind(p, v) {
+                res = Err(err);
+                break;
+            }
+        }
+        if res.is_ok() {
+            self.call_depth += 1;
+            res = self.evaluate(&func.body);
+            self.call_depth -= 1;
+        }
+        for ((p, old), old_function) in std::iter::zip(std::iter::zip(&func.params, shadowed), shadowed_functions) {
+            self.unbind(p);
+            if let Some(v) = old {
+                self.bind(p, v)?;
+            }
+            if let Some(f) = old_function {
+                self.functions.insert(p.clone(), f);
+            }
+        }
+        res
+    }
+
+    fn evaluate(&mut self, node: &SyntaxNode) -> Result<ExprValue, PerqError> {
+        match &node.kind {
+            NodeKind::Text(s) => Ok(TextExpr(String::from_utf8_lossy(s).into_owned())),
+            NodeKind::Constant(s) => {
+                match std::str::from_utf8(s).ok().and_then(parse_literal) {
+                    Some(r) => Ok(ConstExpr(r)),
+                    None => Err(ParseError::new("Malformed number.".to_string(), node.span).into())
+                }
+            },
+            NodeKind::Identifier(s) => {
                 let Ok(key) = std::str::from_utf8(s) else {
-                    return Err("Invalid UTF8.".to_string());
+                    return Err(PerqError::InvalidArgument("Invalid UTF8.".to_string()));
                 };
                 if let Some(x) = self.series_variables.get(key) {
                     return Ok(SeriesExpr(x.clone()));
                 }
+                if let Some(x) = self.laurent_variables.get(key) {
+                    return Ok(LaurentExpr(x.clone()));
+                }
                 if let Some(x) = self.constant_variables.get(key) {
                     return Ok(ConstExpr(x.clone()));
                 }
+                if let Some(x) = self.search_variables.get(key) {
+                    return Ok(SearchExpr(x.clone()));
+                }
+                if let Some(func) = self.functions.get(key) {
+                    return self.call(key, func.clone(), vec![]);
+                }
                 if let Some(x) = self.series_lookup(key) {
                     return Ok(SeriesExpr(x));
                 }
@@ -200,138 +610,856 @@ impl RunTimeEnvironment {
                     }
                     if all_dig {
                         let Ok(a_str) = std::str::from_utf8(&s[1..]) else {
-                            return Err("Invalid UTF8.".to_string());
+                            return Err(PerqError::InvalidArgument("Invalid UTF8.".to_string()));
                         };
                         let Ok(a_num) = a_str.parse() else {
-                            return Err("Failed to parse A number".to_string());
+                            return Err(PerqError::UnknownIdentifier(key.to_string()));
                         };
                         let Some(ind) = self.search_database.a_to_ind.get(&a_num) else {
-                            return Err("Did not find sequence with given A number in database.".to_string());
+                            return Err(PerqError::MissingANumber(a_num));
                         };
                         let mut ret = self.search_database.long_vec[*ind].clone();
                         ret.limit_accuracy(self.default_precision);
                         return Ok(SeriesExpr(ret));
                     }
                 }
-                Err("Identifier not found.".to_string())
+                Err(PerqError::UnknownIdentifier(key.to_string()))
             },
-            SyntaxNode::Series(s) => {
+            NodeKind::Series(s) => {
                 let mut coeff = vec![];
                 for c in s {
                     let ConstExpr(res) = self.evaluate(c)? else {
-                        return Err("Expected const in series definition.".to_string());
+                        return Err(PerqError::InvalidArgument("Expected const in series definition.".to_string()));
                     };
                     coeff.push(res);
                 }
                 Ok(SeriesExpr(Series { seq: coeff }))
             },
-            SyntaxNode::Paren(s) => self.evaluate(s),
-            SyntaxNode::LetStatement(name, expr) => {
+            NodeKind::Paren(s) => self.evaluate(s),
+            NodeKind::LetStatement(name, expr) => {
                 let Ok(key) = std::str::from_utf8(name) else {
-                    return Err("Failed to parse variable name".to_string());
+                    return Err(PerqError::InvalidArgument("Failed to parse variable name".to_string()));
                 };
-                match self.evaluate(expr)? {
-                    SeriesExpr(s) => { self.series_variables.insert(key.to_string(), s); },
-                    ConstExpr(s) => { self.constant_variables.insert(key.to_string(), s); }
-                }
-                Ok(ConstExpr(Default::default()))
+                let val = self.evaluate(expr)?;
+                self.bind(key, val.clone())?;
+                Ok(val)
             }
-            SyntaxNode::RecStatement(name, expr) => {
-                Err("Rec has not been implemented yet!".to_string())
+            NodeKind::RecStatement(lhs, expr) => {
+                let NodeKind::Identifier(name) = &lhs.kind else {
+                    return Err(PerqError::InvalidArgument("Expected identifier on left side of letrec.".to_string()));
+                };
+                let Ok(key) = std::str::from_utf8(name) else {
+                    return Err(PerqError::InvalidArgument("Failed to parse variable name".to_string()));
+                };
+                let rec = SeriesExpr(self.solve_recursive(key, expr)?);
+                self.bind(key, rec.clone())?;
+                Ok(rec)
+            },
+            NodeKind::DefStatement(name, params, body) => {
+                let Ok(key) = std::str::from_utf8(name) else {
+                    return Err(PerqError::InvalidArgument("Failed to parse function name".to_string()));
+                };
+                self.define(key, params, body)
             },
-            SyntaxNode::Application(func, args) => {
+            NodeKind::Application(func, args) => {
                 let Ok(name) = std::str::from_utf8(func) else {
-                    return Err("Failed to parse function name".to_string());
+                    return Err(PerqError::InvalidArgument("Failed to parse function name".to_string()));
                 };
-                let mut arg_vals = vec![];
+                if name == "help" {
+                    return Self::help(args);
+                }
+                let mut vals = vec![];
                 for arg in args {
-                    match self.evaluate(arg)? {
-                        SeriesExpr(s) => arg_vals.push(s),
-                        ConstExpr(c) => arg_vals.push(Series::promote(c))
-                    }
+                    vals.push(self.evaluate(arg)?);
+                }
+                if let Some(func) = self.functions.get(name) {
+                    return self.call(name, func.clone(), vals);
                 }
                 match name {
-                    "help" => { 
-                        return Err("Help not implemented yet.".to_string());
+                    "set_precision" => {
+                        let [ConstExpr(c)] = &vals[..] else {
+                            return Err(PerqError::InvalidArgument("Set precision takes one constant argument.".to_string()));
+                        };
+                        let Ok(prec) = usize::try_from(c.numer()) else {
+                            return Err(PerqError::InvalidArgument("Precision must be a positive integer.".to_string()));
+                        };
+                        if !c.denom().is_one() || prec == 0 {
+                            return Err(PerqError::InvalidArgument("Precision must be a positive integer.".to_string()));
+                        }
+                        self.default_precision = prec;
+                        return Ok(ConstExpr(Rational::from(prec)));
+                    },
+                    "set_echo" => {
+                        let [ConstExpr(c)] = &vals[..] else {
+                            return Err(PerqError::InvalidArgument("Set echo takes one constant argument.".to_string()));
+                        };
+                        self.echo_assignments = !c.is_zero();
+                        return Ok(ConstExpr(Rational::from(self.echo_assignments as u32)));
+                    },
+                    "set_format" => {
+                        let [TextExpr(fmt)] = &vals[..] else {
+                            return Err(PerqError::InvalidArgument("Set format takes a format name in quotes.".to_string()));
+                        };
+                        let Ok(format) = fmt.parse() else {
+                            return Err(PerqError::InvalidArgument(format!("Unknown format {}, use ogf, egf, list, latex or mathml.", fmt)));
+                        };
+                        self.format = format;
+                        return Ok(TextExpr(fmt.clone()));
                     },
-                    "search" => {
-                        if args.len() != 1 {
-                            return Err("Search takes one argument.".to_string());
+                    "set_order" => {
+                        let [ConstExpr(c)] = &vals[..] else {
+                            return Err(PerqError::InvalidArgument("Set order takes one constant argument.".to_string()));
+                        };
+                        self.show_order = !c.is_zero();
+                        return Ok(ConstExpr(Rational::from(self.show_order as u32)));
+                    },
+                    "score" | "anumber" | "formula" => {
+                        let [SearchExpr(res), ConstExpr(i)] = &vals[..] else {
+                            return Err(PerqError::InvalidArgument(format!("{} takes search results and an index.", name)));
+                        };
+                        let entry = Self::search_entry(res, Self::as_index(i)?)?;
+                        return Ok(match name {
+                            "score" => ConstExpr(Rational::from(entry.score)),
+                            "anumber" => ConstExpr(Rational::from(entry.a_number)),
+                            _ => TextExpr(entry.formula.clone())
+                        });
+                    },
+                    "save" | "load" => {
+                        let [TextExpr(file)] = &vals[..] else {
+                            return Err(PerqError::InvalidArgument(format!("{} takes a file name in quotes.", name)));
+                        };
+                        if name == "save" {
+                            self.save(file)?;
+                            return Ok(TextExpr(format!("Saved workspace to {}.", file)));
                         }
-                        let topres = self.search_database.search_full(&arg_vals[0]);
-                        return Err(topres.to_string());
+                        self.load(file)?;
+                        return Ok(TextExpr(format!("Loaded workspace from {}.", file)));
                     },
-                    "set_precision" => {
-                        return Err("Set precision not implemented yet.".to_string());
+                    // Roots of c x^k (1 + ...) are Puiseux series
+                    "sqrt" if vals.len() == 1 && ExprValue::needs_laurent(&vals[0], Operator::Pow, &ConstExpr(Rational::from((1, 2)))) => {
+                        return ExprValue::apply_laurent(vals.remove(0), Operator::Pow, ConstExpr(Rational::from((1, 2))));
+                    },
+                    "coeff" => {
+                        let [val, ConstExpr(i)] = &vals[..] else {
+                            return Err(PerqError::InvalidArgument("Coeff takes a series and an index.".to_string()));
+                        };
+                        return Self::index(val, i);
+                    },
+                    "precision" => {
+                        return match &vals[..] {
+                            [] => Ok(ConstExpr(Rational::from(self.default_precision))),
+                            [SeriesExpr(s)] => Ok(ConstExpr(Rational::from(s.accuracy()))),
+                            _ => Err(PerqError::InvalidArgument("Precision takes no argument or one series.".to_string()))
+                        };
                     },
                     _ => { }
                 }
+                if help::lookup(name).is_some_and(|e| e.kind == help::EntryKind::Construction) {
+                    return self.construct(name, vals);
+                }
+                if let Some(f) = hops::lookup(name) {
+                    return self.hops(name, f, vals);
+                }
+                if let Some(f) = arith::lookup(name) {
+                    let Some(consts) = vals.iter().map(|v| match v {
+                        ConstExpr(c) => Some(c.clone()),
+                        _ => None
+                    }).collect::<Option<Vec<Rational>>>() else {
+                        return Err(PerqError::InvalidArgument(format!("{} takes constant arguments.", name)));
+                    };
+                    if let Some(entry) = help::lookup(name) {
+                        if entry.arity != consts.len() {
+                            return Err(PerqError::InvalidArgument(format!("{} takes {} argument(s).", name, entry.arity)));
+                        }
+                    }
+                    return Ok(ConstExpr(f(&consts)?));
+                }
+                let arg_vals = vals.into_iter().map(|v| self.series_value(v)).collect::<Result<Vec<Series>, PerqError>>()?;
+                if name == "search" {
+                    if args.len() != 1 {
+                        return Err(PerqError::InvalidArgument("Search takes one argument.".to_string()));
+                    }
+                    return Ok(SearchExpr(self.search_database.search_full(&arg_vals[0])));
+                }
+                if let Some(entry) = help::lookup(name) {
+                    if entry.arity != arg_vals.len() {
+                        return Err(PerqError::InvalidArgument(format!("{} takes {} argument(s).", name, entry.arity)));
+                    }
+                }
                 match arg_vals.len() {
                     1 => {
                         let Some(f) = Self::transformation_lookup(name) else {
-                            return Err("Function not found".to_string());
+                            return Err(PerqError::UnknownFunction(name.to_string()));
                         };
+                        Self::check_domain(name, &arg_vals[0])?;
                         Ok(SeriesExpr(f(&arg_vals[0])))
                     },
                     2 => {
                         let Some(f) = Self::convolution_lookup(name) else {
-                            return Err("Function not found".to_string());
+                            return Err(PerqError::UnknownFunction(name.to_string()));
                         };
                         Ok(SeriesExpr(f(&arg_vals[0], &arg_vals[1])))
                     }
-                    _ => Err("Function not found".to_string())
+                    _ => Err(PerqError::UnknownFunction(name.to_string()))
+                }
+            },
+            NodeKind::Index(x, i) => {
+                let val = self.evaluate(x)?;
+                let ConstExpr(i) = self.evaluate(i)? else {
+                    return Err(PerqError::InvalidArgument("Expected constant index.".to_string()));
+                };
+                Self::index(&val, &i)
+            },
+            NodeKind::Slice(x, lo, hi) => {
+                let SeriesExpr(s) = self.evaluate(x)? else {
+                    return Err(PerqError::InvalidArgument("Only series can be sliced.".to_string()));
+                };
+                let lo = match lo {
+                    Some(n) => self.evaluate_index(n)?,
+                    None => 0
+                };
+                let hi = match hi {
+                    Some(n) => self.evaluate_index(n)?,
+                    None => s.accuracy()
+                };
+                if hi > s.accuracy() {
+                    return Err(PerqError::Domain(format!("Only {} terms of the series are known.", s.accuracy())));
                 }
+                if lo >= hi {
+                    return Err(PerqError::InvalidArgument("Slice must contain at least one term.".to_string()));
+                }
+                Ok(SeriesExpr(s.seq[lo..hi].iter().cloned().collect()))
             },
-            SyntaxNode::BinaryOp(x, op, y) => {
+            NodeKind::BinaryOp(x, op, y) => {
                 Ok(ExprValue::apply_binop(self.evaluate(x)?, *op, self.evaluate(y)?)?)
             },
-            SyntaxNode::UnaryOp(op, x) => {
+            NodeKind::UnaryOp(op, x) => {
                 Ok(ExprValue::apply_unop(*op, self.evaluate(x)?)?)
             }
         }
     }
 
-    pub fn repl(&mut self, inp: &mut dyn std::io::BufRead, outp: &mut dyn std::io::Write, interactive: bool) -> std::io::Result<()> {
+    // The workspace is written as a script that rebuilds it. Series keep all known
+    // terms so their precision survives. Search results are not saved, and Laurent
+    // series have no literal to write them back with, so saving them is an error.
+    pub fn save(&self, file: &str) -> Result<(), PerqError> {
+        if !self.laurent_variables.is_empty() {
+            let mut names: Vec<_> = self.laurent_variables.keys().cloned().collect();
+            names.sort();
+            return Err(PerqError::InvalidArgument(format!("Can not save Laurent series {}.", names.join(", "))));
+        }
+        let mut out = format!("# perq workspace\nset_precision({});\n", self.default_precision);
+        let mut constants: Vec<_> = self.constant_variables.iter().collect();
+        constants.sort();
+        for (name, c) in constants {
+            out += &format!("let {} := {};\n", name, c);
+        }
+        let mut series: Vec<_> = self.series_variables.iter().collect();
+        series.sort();
+        for (name, s) in series {
+            let terms: Vec<String> = s.seq.iter().map(|c| c.to_string()).collect();
+            out += &format!("let {} := [{}];\n", name, terms.join(", "));
+        }
+        let mut functions: Vec<_> = self.functions.iter().collect();
+        functions.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, func) in functions {
+            out += &format!("def {}({}) := {};\n", name, func.params.join(", "), func.body);
+        }
+        std::fs::write(file, out)?;
+        Ok(())
+    }
+
+    pub fn load(&mut self, file: &str) -> Result<(), PerqError> {
+        let text = std::fs::read_to_string(file)?;
+        self.execute(&text)?;
+        Ok(())
+    }
+
+    // Built-in names, variables, functions and A-numbers starting with prefix, for tab completion
+    pub fn completions(&self, prefix: &str) -> Vec<String> {
+        let builtins = help::HELP_ENTRIES.iter()
+            .filter(|e| e.kind != help::EntryKind::Operator)
+            .map(|e| e.name.to_string());
+        let mut res: Vec<String> = builtins
+            .chain(self.series_variables.keys().cloned())
+            .chain(self.laurent_variables.keys().cloned())
+            .chain(self.constant_variables.keys().cloned())
+            .chain(self.search_variables.keys().cloned())
+            .chain(self.functions.keys().cloned())
+            .filter(|name| name.starts_with(prefix))
+            .collect();
+        // Only once a digit is typed, there are too many A-numbers to list
+        if prefix.len() > 1 && prefix.starts_with('A') && prefix[1..].bytes().all(|c| c.is_ascii_digit()) {
+            let mut a_nums: Vec<String> = self.search_database.ind_to_a.iter()
+                .map(|a| format!("A{:06}", a))
+                .filter(|a| a.starts_with(prefix))
+                .collect();
+            a_nums.sort();
+            a_nums.truncate(100);
+            res.extend(a_nums);
+        }
+        res.sort();
+        res.dedup();
+        res
+    }
+
+    // Run every statement in the text, stopping at the first error
+    pub fn execute(&mut self, text: &str) -> Result<Vec<ExprValue>, PerqError> {
+        let tokens = lexer::parse_tokens(text.as_bytes())?;
+        let mut res = vec![];
+        for stmt in parser::parse_commands(&tokens)? {
+            res.push(self.evaluate(&stmt.node)?);
+        }
+        Ok(res)
+    }
+
+    // A value as printed, series follow the format and order settings
+    fn show(&self, val: &ExprValue) -> String {
+        match val {
+            SeriesExpr(s) => s.format(self.format, self.show_order),
+            LaurentExpr(l) => l.format(self.format, self.show_order),
+            _ => val.to_string()
+        }
+    }
+
+    // Run the statements of text in order, printing each value. Stops at the first
+    // failing statement and returns its diagnostic with the source underlined.
+    pub fn run_text(&mut self, text: &str, outp: &mut dyn std::io::Write) -> std::io::Result<Option<String>> {
+        let tokens = match lexer::parse_tokens(text.as_bytes()) {
+            Ok(x) => { x },
+            Err(err) => { return Ok(Some(err.render(text.as_bytes()))); }
+        };
+        let syntax_tree = match parser::parse_commands(&tokens) {
+            Ok(x) => { x },
+            Err(err) => { return Ok(Some(err.render(text.as_bytes()))); }
+        };
+        for stmt in syntax_tree {
+            match self.evaluate(&stmt.node) {
+                Ok(x) => {
+                    if stmt.silent {
+                        continue;
+                    }
+                    // Definitions only print when echo is on
+                    let echo = match &stmt.node.kind {
+                        NodeKind::LetStatement(name, _) => Some(format!("{} = {}", String::from_utf8_lossy(name), self.show(&x))),
+                        NodeKind::RecStatement(lhs, _) => Some(match &lhs.kind {
+                            NodeKind::Identifier(name) => format!("{} = {}", String::from_utf8_lossy(name), self.show(&x)),
+                            _ => self.show(&x)
+                        }),
+                        NodeKind::DefStatement(_, _, _) => Some(x.to_string()),
+                        _ => None
+                    };
+                    match echo {
+                        Some(line) if self.echo_assignments => {
+                            outp.write_all(line.as_bytes())?;
+                            outp.write_all(b"\n")?;
+                        },
+                        Some(_) => { },
+                        None => {
+                            outp.write_all(self.show(&x).as_bytes())?;
+                            outp.write_all(b"\n")?;
+                        }
+                    }
+                },
+                Err(msg) => {
+                    // Point at the statement that failed
+                    let err = ParseError::new(msg.to_string(), stmt.node.span);
+                    return Ok(Some(err.render(text.as_bytes())));
+                }
+            };
+        }
+        Ok(None)
+    }
+
+    // Lines are collected until the statement is complete, see is_incomplete.
+    // Returns whether every statement succeeded.
+    pub fn repl(&mut self, inp: &mut dyn std::io::BufRead, outp: &mut dyn std::io::Write, interactive: bool) -> std::io::Result<bool> {
         let mut buf: String = Default::default();
+        let mut ok = true;
         loop {
             buf.clear();
-            match inp.read_line(&mut buf) {
-                Err(_) => { return Ok(()); },
-                Ok(0) => { return Ok(()); }
+            loop {
+                match inp.read_line(&mut buf) {
+                    Err(_) | Ok(0) => { break; },
+                    _ => { }
+                }
+                if !is_incomplete(&buf) { break; }
+            }
+            if buf.is_empty() {
+                return Ok(ok);
+            }
+            if let Some(diagnostic) = self.run_text(&buf, outp)? {
+                ok = false;
+                outp.write_all(diagnostic.as_bytes())?;
+                outp.write_all(b"\n")?;
+            }
+            if interactive { outp.flush()?; }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::runtime::{RunTimeEnvironment, ExprValue};
+    use crate::{Series, SeriesFormat, PerqError};
+    use crate::oeis::SeqDB;
+    use rug::Rational;
+
+    fn test_env() -> RunTimeEnvironment {
+        RunTimeEnvironment {
+            constant_variables: Default::default(),
+            series_variables: Default::default(),
+            laurent_variables: Default::default(),
+            search_variables: Default::default(),
+            search_database: Default::default(),
+            default_precision: 16,
+            echo_assignments: false,
+            format: SeriesFormat::Ogf,
+            show_order: false,
+            functions: Default::default(),
+            call_depth: 0,
+        }
+    }
+
+    fn run(rt: &mut RunTimeEnvironment, text: &str) -> Result<ExprValue, PerqError> {
+        Ok(rt.execute(text)?.pop().expect("No commands."))
+    }
+
+    fn series_var(rt: &RunTimeEnvironment, name: &str) -> Series {
+        rt.series_variables[name].clone()
+    }
+
+    #[test]
+    fn test_letrec_catalan() {
+        let mut rt = test_env();
+        run(&mut rt, "letrec f := 1 + rshift(f^2)").unwrap();
+        let catalan: Series = "1,1,2,5,14,42,132,429,1430,4862,16796,58786,208012,742900,2674440,9694845".parse().unwrap();
+        assert_eq!(series_var(&rt, "f"), catalan);
+        // Rooted labelled trees, n^(n-1) as EGF, the precision is restored after the rounds
+        run(&mut rt, "set_precision(40); letrec t := x * exp(t)").unwrap();
+        assert_eq!(rt.default_precision, 40);
+        assert_eq!(series_var(&rt, "t").seq.len(), 40);
+        assert!(matches!(run(&mut rt, "laplace(t)[39] - 39^38"), Ok(ExprValue::ConstExpr(c)) if c == 0));
+    }
+
+    #[test]
+    fn test_letrec_motzkin() {
+        let mut rt = test_env();
+        run(&mut rt, "letrec m := 1 + rshift(m) + rshift(rshift(m^2))").unwrap();
+        let motzkin: Series = "1,1,2,4,9,21,51,127,323,835,2188,5798,15511,41835,113634,310572".parse().unwrap();
+        assert_eq!(series_var(&rt, "m"), motzkin);
+    }
+
+    #[test]
+    fn test_set_precision() {
+        let mut rt = test_env();
+        run(&mut rt, "set_precision(24)").unwrap();
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "sin").unwrap() else { panic!() };
+        assert_eq!(s.seq.len(), 24);
+        assert!(run(&mut rt, "set_precision(0)").is_err());
+        // One term is the smallest precision, everything built from x still works there
+        run(&mut rt, "set_precision(1)").unwrap();
+        for text in ["x", "sin", "tan @ x", "1/(1 + x)", "exp(x)", "letrec f := 1 + x * f^2", "euler(x)"] {
+            assert!(matches!(run(&mut rt, text), Ok(ExprValue::SeriesExpr(s)) if s.seq.len() == 1), "{}", text);
+        }
+        run(&mut rt, "set_precision(24)").unwrap();
+        assert!(run(&mut rt, "set_precision(1/2)").is_err());
+        let ExprValue::ConstExpr(c) = run(&mut rt, "precision()").unwrap() else { panic!() };
+        assert_eq!(c, 24);
+    }
+
+    #[test]
+    fn test_mixed_precision() {
+        let mut rt = test_env();
+        run(&mut rt, "let s := [1, 2, 3] + cos").unwrap();
+        assert_eq!(series_var(&rt, "s").seq.len(), 3);
+        run(&mut rt, "let t := cos / [1, 1, 1, 1]").unwrap();
+        assert_eq!(series_var(&rt, "t").seq.len(), 4);
+        let ExprValue::ConstExpr(c) = run(&mut rt, "precision(partial_sums(t))").unwrap() else { panic!() };
+        assert_eq!(c, 4);
+    }
+
+    #[test]
+    fn test_help_registry() {
+        use crate::help::{HELP_ENTRIES, EntryKind};
+        let rt = test_env();
+        for entry in HELP_ENTRIES {
+            match entry.kind {
+                EntryKind::Transform => assert!(RunTimeEnvironment::transformation_lookup(entry.name).is_some()),
+                EntryKind::Convolution => assert!(RunTimeEnvironment::convolution_lookup(entry.name).is_some()),
+                EntryKind::Construction => assert!(RunTimeEnvironment::construction_lookup(entry.name).is_some() || RunTimeEnvironment::sized_construction_lookup(entry.name).is_some()),
+                EntryKind::Hops => assert!(crate::hops::lookup(entry.name).is_some()),
+                EntryKind::KnownSeries => assert!(rt.series_lookup(entry.name).is_some()),
+                EntryKind::Arithmetic => assert!(crate::arith::lookup(entry.name).is_some()),
                 _ => { }
             }
-            let tokens = match lexer::parse_tokens(buf.as_bytes()) {
-                Ok(x) => { x },
-                Err(msg) => {
-                    outp.write(msg.as_bytes())?;
-                    outp.write(b"\n")?;
-                    if interactive { outp.flush()?; }
-                    continue;
-                }
-            };
-            let syntax_tree = match parser::parse_commands(&tokens) {
-                Ok(x) => { x },
-                Err(msg) => {
-                    outp.write(msg.as_bytes())?;
-                    outp.write(b"\n")?;
-                    if interactive { outp.flush()?; }
-                    continue;
-                }
-            };
-            for node in syntax_tree {
-                match self.evaluate(&node) {
-                    Ok(x) => { 
-                        outp.write(x.to_string().as_bytes())?;
-                        outp.write(b"\n")?;
-                        if interactive { outp.flush()?; }
-                    },
-                    Err(msg) => {
-                        outp.write(msg.as_bytes())?;
-                        outp.write(b"\n")?;
-                        if interactive { outp.flush()?; }
-                    }
-                };
+        }
+    }
+
+    #[test]
+    fn test_help() {
+        let mut rt = test_env();
+        let ExprValue::TextExpr(all) = run(&mut rt, "help()").unwrap() else { panic!() };
+        assert!(all.contains("bous_inv(a)"));
+        let ExprValue::TextExpr(one) = run(&mut rt, "help(inverse)").unwrap() else { panic!() };
+        assert!(one.contains("requires a(0)=0"));
+        assert!(run(&mut rt, "help(nonexistent)").is_err());
+        assert!(run(&mut rt, "derive([1, 2], [3, 4])").is_err());
+    }
+
+    #[test]
+    fn test_search_results() {
+        let stripped = "# header\nA000108 ,1,1,2,5,14,42,132,429,1430,4862,16796,58786,208012,742900,2674440,9694845,\nA000045 ,0,1,1,2,3,5,8,13,21,34,55,89,144,233,377,610,\n";
+        let mut rt = test_env();
+        rt.search_database = SeqDB::from_reader(stripped.as_bytes()).unwrap();
+        run(&mut rt, "let r := search(A108)").unwrap();
+        let ExprValue::SearchExpr(res) = run(&mut rt, "r").unwrap() else { panic!() };
+        assert!(!res.is_empty());
+        let ExprValue::ConstExpr(a) = run(&mut rt, "anumber(r, 0)").unwrap() else { panic!() };
+        assert_eq!(a, 108);
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "r[0]").unwrap() else { panic!() };
+        assert_eq!(s[3], 5);
+        assert!(matches!(run(&mut rt, "formula(r, 0)").unwrap(), ExprValue::TextExpr(_)));
+        assert!(matches!(run(&mut rt, "score(r, 0)").unwrap(), ExprValue::ConstExpr(_)));
+        assert!(run(&mut rt, "r[100]").is_err());
+    }
+
+    #[test]
+    fn test_operator_evaluation() {
+        let mut rt = test_env();
+        for (text, val) in [("10 - 3 - 2", 5), ("2 ^ 3 ^ 2", 512), ("-2 ^ 2", -4), ("64 / 4 / 2", 8), ("2 * -3 + 1", -5)] {
+            let ExprValue::ConstExpr(c) = run(&mut rt, text).unwrap() else { panic!() };
+            assert_eq!(c, val, "{}", text);
+        }
+    }
+
+    #[test]
+    fn test_letrec_diverges() {
+        let mut rt = test_env();
+        assert!(run(&mut rt, "letrec f := 1 + f").is_err());
+        assert!(rt.series_variables.get("f").is_none());
+    }
+
+    #[test]
+    fn test_repl_diagnostics() {
+        let mut rt = test_env();
+        let mut out = vec![];
+        assert!(!rt.repl(&mut "1 + )2\n1; foo + 1\n".as_bytes(), &mut out, false).unwrap());
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out, "1 + )2\n    ^\nLine 1, column 5: Expected expression, found )\n1; foo + 1\n   ^^^^^^^\nLine 1, column 4: Identifier foo not found.\n");
+        assert!(rt.repl(&mut "1 + 2\n".as_bytes(), &mut vec![], false).unwrap());
+    }
+
+    #[test]
+    fn test_continuation() {
+        let mut rt = test_env();
+        let mut out = vec![];
+        let inp = "letrec f := 1 +\n  rshift(f\n  )\nf[0..3] # comment\n/* spans\nlines */ [1,\n2]\n(1 + \n";
+        rt.repl(&mut inp.as_bytes(), &mut out, false).unwrap();
+        let out = String::from_utf8(out).unwrap();
+        assert_eq!(out, "1+x+x^2\n1+2x\n(1 + \n    ^\nLine 1, column 5: Expected expression, found end of input\n");
+        assert!(!super::is_incomplete("1 + 2 # (\n"));
+        assert!(super::is_incomplete("/* let"));
+    }
+
+    #[test]
+    fn test_error_kinds() {
+        let mut rt = test_env();
+        rt.search_database = SeqDB::from_reader("A000045 ,0,1,1,2,3,5,8,13,21,34,55,89,144,\n".as_bytes()).unwrap();
+        assert!(matches!(run(&mut rt, "1 / 0"), Err(PerqError::DivisionByZero)));
+        assert!(matches!(run(&mut rt, "[1, 2] ./ [1, 0]"), Err(PerqError::DivisionByZero)));
+        assert!(matches!(run(&mut rt, "[1, 2] / [0, 0]"), Err(PerqError::DivisionByZero)));
+        assert!(matches!(run(&mut rt, "[2, 1]^(1/2)"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "[1, 2] @ [1, 1]"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "x @ []"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "[] @ x"), Ok(ExprValue::SeriesExpr(s)) if s.seq.is_empty()));
+        assert!(matches!(run(&mut rt, "foo + 1"), Err(PerqError::UnknownIdentifier(name)) if name == "foo"));
+        assert!(matches!(run(&mut rt, "foo(1)"), Err(PerqError::UnknownFunction(name)) if name == "foo"));
+        assert!(matches!(run(&mut rt, "A000001"), Err(PerqError::MissingANumber(1))));
+        assert!(run(&mut rt, "A45").is_ok());
+        assert!(matches!(run(&mut rt, "(1 +"), Err(PerqError::Parse(_))));
+        assert!(matches!(run(&mut rt, "1 $ 2"), Err(PerqError::Parse(_))));
+        assert!(matches!(run(&mut rt, "[1] @ 2"), Err(PerqError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_database_errors() {
+        let text = "# comment\nA000045 ,0,1,1,2,3,5,8,13,21,34,55,89,144,\nA000046 0,1,2,\n";
+        assert!(matches!(SeqDB::from_reader(text.as_bytes()), Err(PerqError::Database { line: 3, .. })));
+        let text = "Axyz ,1,2,\n";
+        assert!(matches!(SeqDB::from_reader(text.as_bytes()), Err(PerqError::Database { line: 1, .. })));
+        let text = "A000045 ,0,1,1,2,3,5,8,13,2x1,34,55,89,144,\n";
+        assert!(matches!(SeqDB::from_reader(text.as_bytes()), Err(PerqError::Database { line: 1, .. })));
+    }
+
+    #[test]
+    fn test_run_script() {
+        let mut rt = test_env();
+        let mut out = vec![];
+        let script = "let a := [1, 2, 3]\na + 1\n\nderive(a) * b\na";
+        let diagnostic = rt.run_text(script, &mut out).unwrap().unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "2+2x+3x^2\n");
+        assert_eq!(diagnostic, "derive(a) * b\n^^^^^^^^^^^^^\nLine 4, column 1: Identifier b not found.");
+        assert!(rt.run_text("a; 2 * a", &mut vec![]).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_silent_statements() {
+        let mut rt = test_env();
+        let mut out = vec![];
+        let script = "let a := [1, 2, 3]\na + 1;\nletrec f := 1 + rshift(f); a\nset_echo(1);\nlet b := 2\nlet c := 3;";
+        assert!(rt.run_text(script, &mut out).unwrap().is_none());
+        assert_eq!(String::from_utf8(out).unwrap(), "1+2x+3x^2\nb = 2\n");
+        assert!(rt.constant_variables.contains_key("c"));
+    }
+
+    #[test]
+    fn test_literals() {
+        let mut rt = test_env();
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "[1/2, -3, 1/6, -0.25, 1e3, 2.5e-2, -7/4]").unwrap() else { panic!() };
+        let expected: Vec<Rational> = [(1, 2), (-3, 1), (1, 6), (-1, 4), (1000, 1), (1, 40), (-7, 4)].iter().map(|&(p, q)| Rational::from((p, q))).collect();
+        assert_eq!(s.seq, expected);
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "[1, 1]^(1/2)").unwrap() else { panic!() };
+        assert_eq!(s.seq, [Rational::from(1), Rational::from((1, 2))]);
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "[1, 1]^1/2").unwrap() else { panic!() };
+        assert_eq!(s.seq, [Rational::from((1, 2)), Rational::from((1, 2))]);
+        assert!(matches!(run(&mut rt, "1/0"), Err(PerqError::DivisionByZero)));
+        assert!(matches!(run(&mut rt, "1e99999999999"), Err(PerqError::Parse(_))));
+    }
+
+    #[test]
+    fn test_user_functions() {
+        let mut rt = test_env();
+        let mut out = vec![];
+        let script = "def g(a, b) := exp_mul(a, b) + a\nlet a := 5\ng([1, 1, 1], [1, 2])\na\ndef one() := [1, 1]; one * g(one, 1)";
+        assert!(rt.run_text(script, &mut out).unwrap().is_none());
+        assert_eq!(String::from_utf8(out).unwrap(), "2+4x\n5\n2+4x\n");
+        assert!(run(&mut rt, "g(1)").is_err());
+        assert!(run(&mut rt, "def sqrt(a) := a").is_err());
+        assert!(run(&mut rt, "def h(a, a) := a").is_err());
+        run(&mut rt, "def loop(a) := loop(a)").unwrap();
+        assert!(matches!(run(&mut rt, "loop(1)"), Err(PerqError::Domain(_))));
+        assert_eq!(rt.call_depth, 0);
+        // A parameter named like a function hides it only during the call
+        run(&mut rt, "def f(a) := a + 1; def k(f) := f; k(1)").unwrap();
+        assert!(matches!(run(&mut rt, "f(2)"), Ok(ExprValue::ConstExpr(c)) if c == 3));
+        run(&mut rt, "let g := 2").unwrap();
+        assert!(run(&mut rt, "g([1], [1])").is_err());
+    }
+
+    #[test]
+    fn test_indexing() {
+        let mut rt = test_env();
+        rt.search_database = SeqDB::from_reader("A000108 ,1,1,2,5,14,42,132,429,1430,4862,16796,58786,208012,742900,\n".as_bytes()).unwrap();
+        let ExprValue::ConstExpr(c) = run(&mut rt, "A000108[10]").unwrap() else { panic!() };
+        assert_eq!(c, 16796);
+        let ExprValue::ConstExpr(c) = run(&mut rt, "coeff(A108, 3) + [1, 2, 3][1]").unwrap() else { panic!() };
+        assert_eq!(c, 7);
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "A108[2..5]").unwrap() else { panic!() };
+        assert_eq!(s.seq, [2, 5, 14].map(Rational::from));
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "(A108 * 2)[..2]").unwrap() else { panic!() };
+        assert_eq!(s.seq, [2, 2].map(Rational::from));
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "A108[12..]").unwrap() else { panic!() };
+        assert_eq!(s.seq.len(), 2);
+        assert!(matches!(run(&mut rt, "A108[16]"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "A108[1/2]"), Err(PerqError::InvalidArgument(_))));
+        assert!(run(&mut rt, "A108[3..3]").is_err());
+        assert!(run(&mut rt, "A108[0..20]").is_err());
+        assert!(run(&mut rt, "5[0]").is_err());
+    }
+
+    #[test]
+    fn test_series_variable() {
+        let mut rt = test_env();
+        let series = |rt: &mut RunTimeEnvironment, text: &str| {
+            let ExprValue::SeriesExpr(s) = run(rt, text).unwrap() else { panic!() };
+            s.seq
+        };
+        let fib = series(&mut rt, "1/(1 - x - x^2)");
+        assert_eq!(fib.len(), 16);
+        assert_eq!(fib[..8], [1, 1, 2, 3, 5, 8, 13, 21].map(Rational::from));
+        assert_eq!(series(&mut rt, "1/(1-x)^3")[..5], [1, 3, 6, 10, 15].map(Rational::from));
+        assert_eq!(series(&mut rt, "log(1/(1-x))")[..4], [0, 1, 2, 3].map(|n| if n == 0 { Rational::new() } else { Rational::from((1, n)) }));
+        assert_eq!(series(&mut rt, "laplace(exp(x + x^2/2))")[..6], [1, 1, 2, 4, 10, 26].map(Rational::from));
+        assert_eq!(series(&mut rt, "(1/(1-x)) @ (x/(1-x))")[..5], [1, 1, 2, 4, 8].map(Rational::from));
+        run(&mut rt, "set_precision(5)").unwrap();
+        assert_eq!(series(&mut rt, "x").len(), 5);
+        assert!(run(&mut rt, "let x := 3").is_err());
+        assert!(run(&mut rt, "def f(x) := x").is_err());
+        assert!(matches!(run(&mut rt, "log(2 + x)"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "exp(1 + x)"), Err(PerqError::Domain(_))));
+        for f in ["exp([])", "log([])", "log_derive([])", "inverse([0])"] {
+            assert!(matches!(run(&mut rt, f), Err(PerqError::Domain(_))), "{}", f);
+        }
+        for f in ["[]^(1/2)", "[]^-1", "sqrt([])", "exp_integ([])"] {
+            assert!(matches!(run(&mut rt, f), Ok(ExprValue::SeriesExpr(s)) if s.seq.is_empty()), "{}", f);
+        }
+        assert!(matches!(run(&mut rt, "sqrt(2*x)"), Err(PerqError::Domain(_))));
+        // Only the constant term is known at precision 1
+        run(&mut rt, "set_precision(1)").unwrap();
+        for (f, c) in [("x", 0), ("sin", 0), ("tan", 0), ("cos", 1), ("expx", 1), ("log1px", 0)] {
+            assert_eq!(series(&mut rt, f), [Rational::from(c)], "{}", f);
+        }
+    }
+
+    #[test]
+    fn test_constructions() {
+        let mut rt = test_env();
+        // Rooted unlabelled trees, T = x mset(T)
+        run(&mut rt, "set_precision(10); letrec t := x * mset(t)").unwrap();
+        assert_eq!(series_var(&rt, "t").seq, [0, 1, 1, 2, 4, 9, 20, 48, 115, 286].map(Rational::from));
+        let ExprValue::SeriesExpr(bell) = run(&mut rt, "laplace(set(set(x) - 1))").unwrap() else { panic!() };
+        assert_eq!(bell.seq, [1, 1, 2, 5, 15, 52, 203, 877, 4140, 21147].map(Rational::from));
+        let ExprValue::SeriesExpr(parts) = run(&mut rt, "mset_k(x/(1-x), 3)").unwrap() else { panic!() };
+        assert_eq!(parts.seq[..8], [0, 0, 0, 1, 1, 2, 3, 4].map(Rational::from));
+        assert!(matches!(run(&mut rt, "seq(1 + x)"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "seq([])"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "mset_k([], 2)"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "cyc_k(x, 0)"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "set_k(x)"), Err(PerqError::InvalidArgument(_))));
+        assert!(run(&mut rt, "def set(a) := a").is_err());
+    }
+
+    #[test]
+    fn test_hops() {
+        let mut rt = test_env();
+        run(&mut rt, "set_precision(8); f = 1 + x*f^2").unwrap();
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "BINOMIAL(f)").unwrap() else { panic!() };
+        assert_eq!(s.seq, [1, 2, 5, 15, 51, 188, 731, 2950].map(Rational::from));
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "LEFT({1, 2, 3, 4})").unwrap() else { panic!() };
+        assert_eq!(s.seq, [2, 3, 4].map(Rational::from));
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "BINOMIAL({1, 1, 1, 1}, 2)").unwrap() else { panic!() };
+        assert_eq!(s.seq, [1, 3, 9, 27].map(Rational::from));
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "BISECT0(AERATE({1, 2, 3, 4, 5, 6}, 1))").unwrap() else { panic!() };
+        assert_eq!(s.seq, [1, 2, 3].map(Rational::from));
+        assert!(matches!(run(&mut rt, "REVERT([0, 1])"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "AERATE(f, 1/2)"), Err(PerqError::InvalidArgument(_))));
+        assert!(matches!(run(&mut rt, "AERATE(f, 18446744073709551615)"), Err(PerqError::InvalidArgument(_))));
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "AERATE({1, 2, 3}, 18446744073709551614)").unwrap() else { panic!() };
+        assert_eq!(s.seq, [1, 0, 0].map(Rational::from));
+        assert!(matches!(run(&mut rt, "LEFT(f, 1)"), Err(PerqError::InvalidArgument(_))));
+        // Transforms of an empty series know no terms, or fail if they need a(0).
+        // RIGHT still knows its leading 1.
+        for entry in crate::help::HELP_ENTRIES.iter().filter(|e| e.kind == crate::help::EntryKind::Hops) {
+            match run(&mut rt, &format!("{}([])", entry.name)) {
+                Ok(ExprValue::SeriesExpr(s)) => assert_eq!(s.seq.len(), (entry.name == "RIGHT") as usize, "{}", entry.name),
+                res => assert!(matches!(res, Err(PerqError::Domain(_))) && entry.condition.is_some(), "{}", entry.name)
             }
         }
     }
+
+    #[test]
+    fn test_laurent() {
+        let mut rt = test_env();
+        run(&mut rt, "set_precision(6)").unwrap();
+        assert_eq!(run(&mut rt, "1/(x - x^2)").unwrap().to_string(), "x^-1+1+x+x^2+x^3");
+        assert_eq!(run(&mut rt, "x^-2").unwrap().to_string(), "x^-2");
+        assert_eq!(run(&mut rt, "sqrt(x + x^2)").unwrap().to_string(), "x^(1/2)+1/2x^(3/2)-1/8x^(5/2)+1/16x^(7/2)-5/128x^(9/2)");
+        assert_eq!(run(&mut rt, "(4*x^2)^(1/2)").unwrap().to_string(), "2x");
+        run(&mut rt, "let f := 1/(x - x^2); set_order(1)").unwrap();
+        assert!(matches!(run(&mut rt, "x * f - 1/(1 - x)").unwrap(), ExprValue::SeriesExpr(s) if s.seq.iter().all(|c| *c == 0)));
+        let mut out = vec![];
+        rt.run_text("f + 1", &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "x^-1+2+x+x^2+x^3 + O(x^4)\n");
+        assert!(matches!(run(&mut rt, "f[0]"), Err(PerqError::InvalidArgument(_))));
+        assert!(matches!(run(&mut rt, "x^(1/65537) + x^(1/65539)"), Err(PerqError::Domain(_))));
+        for text in ["f @ x", "x @ f", "f .* x", "x ./ f"] {
+            assert!(matches!(run(&mut rt, text), Err(PerqError::InvalidArgument(msg)) if msg.ends_with("not supported for Laurent series.")), "{}", text);
+        }
+        let mut out = vec![];
+        rt.run_text("set_order(0); set_format(\"latex\"); sqrt(x)/x\nset_format(\"list\"); f", &mut out).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "x^{-\\frac{1}{2}}\nx^-1 in steps of x: 1, 1, 1, 1, 1\n");
+    }
+
+    #[test]
+    fn test_save_load() {
+        let file = std::env::temp_dir().join(format!("perq_workspace_{}.perq", std::process::id()));
+        let file = file.to_str().unwrap();
+        let mut rt = test_env();
+        let script = format!("set_precision(6); let c := -3/4; let s := [1, -1/2, 0, 7]; let t := 1/(1-x); def g(a, b) := exp_mul(a, b) + -a[1..]\nsave(\"{}\")", file);
+        assert!(rt.run_text(&script, &mut vec![]).unwrap().is_none());
+        let saved = std::fs::read_to_string(file).unwrap();
+        assert!(saved.contains("let s := [1, -1/2, 0, 7];"));
+        assert!(saved.contains("def g(a, b) := exp_mul(a, b) + -a[1..];"));
+        let mut other = test_env();
+        let ExprValue::TextExpr(_) = run(&mut other, &format!("load(\"{}\")", file)).unwrap() else { panic!() };
+        std::fs::remove_file(file).unwrap();
+        assert_eq!(other.default_precision, 6);
+        assert_eq!(other.constant_variables["c"], Rational::from((-3, 4)));
+        assert_eq!(series_var(&other, "s"), series_var(&rt, "s"));
+        assert_eq!(series_var(&other, "t").seq.len(), 6);
+        let ExprValue::SeriesExpr(g) = run(&mut other, "g(s, t)").unwrap() else { panic!() };
+        let ExprValue::SeriesExpr(h) = run(&mut rt, "g(s, t)").unwrap() else { panic!() };
+        assert_eq!(g, h);
+        assert!(matches!(run(&mut other, "load(\"/nonexistent/perq\")"), Err(PerqError::Io(_))));
+        assert!(run(&mut other, "save(s)").is_err());
+        run(&mut other, "let l := 1/x").unwrap();
+        assert!(matches!(run(&mut other, &format!("save(\"{}\")", file)), Err(PerqError::InvalidArgument(msg)) if msg.contains(" l.")));
+        assert!(std::fs::metadata(file).is_err());
+    }
+
+    #[test]
+    fn test_constant_functions() {
+        let mut rt = test_env();
+        let c = |rt: &mut RunTimeEnvironment, text: &str| match run(rt, text) {
+            Ok(ExprValue::ConstExpr(c)) => c.to_string(),
+            res => panic!("{}: {:?}", text, res)
+        };
+        assert_eq!(c(&mut rt, "5! + 0!"), "121");
+        assert_eq!(c(&mut rt, "-3! * 2"), "-12");
+        assert_eq!(c(&mut rt, "binomial(10, 3) + binomial(-2, 3) + binomial(4, -1)"), "116");
+        assert_eq!(c(&mut rt, "binomial(1/2, 2)"), "-1/8");
+        assert_eq!(c(&mut rt, "gcd(12, -18) + lcm(4, 6)"), "18");
+        assert_eq!(c(&mut rt, "floor(-7/2) + ceil(7/2) + numer(-6/4) + denom(-6/4)"), "-1");
+        assert_eq!(c(&mut rt, "mod(-7, 3) + mod(7/2, 1)"), "5/2");
+        assert_eq!(c(&mut rt, "2^100"), "1267650600228229401496703205376");
+        assert_eq!(c(&mut rt, "(-1)^(10^30 + 1) + 1^(-10^40) + 0^(10^20)"), "0");
+        assert_eq!(c(&mut rt, "(8/27)^(-2/3) + (-8)^(1/3)"), "1/4");
+        let ExprValue::SeriesExpr(s) = run(&mut rt, "[binomial(5, 0), binomial(5, 1), 3!/2]").unwrap() else { panic!() };
+        assert_eq!(s.seq, [1, 5, 3]);
+        assert!(matches!(run(&mut rt, "(-1)!"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "(1/2)!"), Err(PerqError::InvalidArgument(_))));
+        assert!(matches!(run(&mut rt, "gcd(1/2, 3)"), Err(PerqError::InvalidArgument(_))));
+        assert!(matches!(run(&mut rt, "mod(3, 0)"), Err(PerqError::DivisionByZero)));
+        assert!(matches!(run(&mut rt, "0^-1"), Err(PerqError::DivisionByZero)));
+        assert!(matches!(run(&mut rt, "2^(1/2)"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "(-4)^(1/2)"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "2^(10^20)"), Err(PerqError::Domain(_))));
+        assert!(matches!(run(&mut rt, "floor([1, 2])"), Err(PerqError::InvalidArgument(_))));
+        assert!(matches!(run(&mut rt, "gcd(4)"), Err(PerqError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_formats() {
+        let mut rt = test_env();
+        let mut out = vec![];
+        let script = "let s := [1, -1, 1/2, 0, 3]\ns\nset_format(\"egf\"); s\nset_format(\"list\"); s\nset_format(\"latex\"); s\nset_format(\"mathml\"); [0, -2]\nset_order(1); set_format(\"ogf\"); s\n[0]\nset_format(\"latex\"); s[..2]\n2";
+        assert!(rt.run_text(script, &mut out).unwrap().is_none());
+        assert_eq!(String::from_utf8(out).unwrap(), [
+            "1-x+1/2x^2+3x^4",
+            "1-x+x^2/2!+72x^4/4!",
+            "1, -1, 1/2, 0, 3",
+            "1 - x + \\frac{1}{2}x^{2} + 3x^{4}",
+            "<math><mo>-</mo><mn>2</mn><mi>x</mi></math>",
+            "1-x+1/2x^2+3x^4 + O(x^5)",
+            "O(x^1)",
+            "1 - x + O(x^{2})",
+            "2\n"
+        ].join("\n"));
+        assert!(matches!(run(&mut rt, "set_format(\"tex\")"), Err(PerqError::InvalidArgument(_))));
+        assert!(matches!(run(&mut rt, "set_format(1)"), Err(PerqError::InvalidArgument(_))));
+    }
+
+    #[test]
+    fn test_completions() {
+        let mut rt = test_env();
+        rt.search_database = SeqDB::from_reader("A000045 ,0,1,1,2,3,5,8,13,21,34,55,89,144,\nA000108 ,1,1,2,5,14,42,132,429,1430,4862,16796,58786,\n".as_bytes()).unwrap();
+        run(&mut rt, "let lahs := lah([1, 2]); let laps := 3; def lap(a) := a").unwrap();
+        assert_eq!(rt.completions("la"), ["lah", "lah_inv", "lahs", "lap", "laplace", "laplace_inv", "laps"]);
+        assert_eq!(rt.completions("A0001"), ["A000108"]);
+        assert_eq!(rt.completions("A00"), ["A000045", "A000108"]);
+        assert_eq!(rt.completions("A"), ["AERATE", "AERATE1", "AERATE2"]);
+        assert!(rt.completions("").contains(&"exp_mul".to_string()));
+        assert!(!rt.completions("").contains(&"point_mul".to_string()));
+    }
 }
@@ -70,6 +70,7 @@ impl<'a, 'b> Add<&'a Series> for &'b Series {
 impl<'a> AddAssign<&'a Series> for Series {
     #[inline]
     fn add_assign(&mut self, other: &'a Series) {
+        self.seq.truncate(other.seq.len());
         zip(self.seq.iter_mut(), other.seq.iter()).for_each(|(x, y)| *x += y);
     }
 }
@@ -99,6 +100,7 @@ impl<'a, 'b> Sub<&'a Series> for &'b Series {
 impl<'a> SubAssign<&'a Series> for Series {
     #[inline]
     fn sub_assign(&mut self, other: &'a Series) {
+        self.seq.truncate(other.seq.len());
         zip(self.seq.iter_mut(), other.seq.iter()).for_each(|(x, y)| *x -= y);
     }
 }
@@ -133,6 +135,7 @@ impl<'a> DivAssign<&'a Series> for Series {
     #[inline]
     fn div_assign(&mut self, other: &'a Series) {
         let n = min(self.seq.len(), other.seq.len());
+        self.seq.truncate(n);
         for i in 0..n {
             self.seq[i] /= &other.seq[0];
             for j in (i+1)..n {
@@ -228,26 +231,121 @@ impl std::str::FromStr for Series {
     }
 }
 
-impl std::fmt::Display for Series {
-    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        let mut fst = true;
+// Ways to print a series, Ogf is the Display format
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SeriesFormat {
+    #[default]
+    Ogf,
+    // Sum of a(n) x^n/n!, where a(n) is n! times coefficient n
+    Egf,
+    // Comma separated coefficients as in the OEIS
+    List,
+    Latex,
+    MathMl,
+}
+
+impl std::str::FromStr for SeriesFormat {
+    type Err = ();
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ogf" => Ok(SeriesFormat::Ogf),
+            "egf" => Ok(SeriesFormat::Egf),
+            "list" => Ok(SeriesFormat::List),
+            "latex" => Ok(SeriesFormat::Latex),
+            "mathml" => Ok(SeriesFormat::MathMl),
+            _ => Err(())
+        }
+    }
+}
+
+impl Series {
+    // With order set the unknown tail is shown, as + O(x^n) or a trailing ... in a list
+    pub fn format(&self, fmt: SeriesFormat, order: bool) -> String {
+        let n = self.seq.len();
+        match fmt {
+            SeriesFormat::Ogf | SeriesFormat::Egf => {
+                let mut res = self.plain(fmt == SeriesFormat::Egf);
+                if order {
+                    res = if res == "0" { format!("O(x^{})", n) } else { format!("{} + O(x^{})", res, n) };
+                }
+                res
+            },
+            SeriesFormat::List => {
+                let mut terms: Vec<String> = self.seq.iter().map(|c| c.to_string()).collect();
+                if order { terms.push("...".to_string()); }
+                terms.join(", ")
+            },
+            SeriesFormat::Latex => {
+                let frac = |c: &Rational| if c.denom() == &1 { c.numer().to_string() } else { format!("\\frac{{{}}}{{{}}}", c.numer(), c.denom()) };
+                let power = |i: usize| if i == 1 { "x".to_string() } else { format!("x^{{{}}}", i) };
+                let mut res = self.signed_terms(|neg, c, i, fst| {
+                    let sign = match (neg, fst) { (true, true) => "-", (true, false) => " - ", (false, true) => "", (false, false) => " + " };
+                    match (i, c == &1) {
+                        (0, _) => format!("{}{}", sign, frac(c)),
+                        (_, true) => format!("{}{}", sign, power(i)),
+                        (_, false) => format!("{}{}{}", sign, frac(c), power(i))
+                    }
+                }, "0");
+                if order {
+                    res = if res == "0" { format!("O({})", power(n)) } else { format!("{} + O({})", res, power(n)) };
+                }
+                res
+            },
+            SeriesFormat::MathMl => {
+                let num = |c: &Rational| if c.denom() == &1 { format!("<mn>{}</mn>", c.numer()) } else { format!("<mfrac><mn>{}</mn><mn>{}</mn></mfrac>", c.numer(), c.denom()) };
+                let power = |i: usize| if i == 1 { "<mi>x</mi>".to_string() } else { format!("<msup><mi>x</mi><mn>{}</mn></msup>", i) };
+                let mut res = self.signed_terms(|neg, c, i, fst| {
+                    let sign = if neg { "<mo>-</mo>" } else if fst { "" } else { "<mo>+</mo>" };
+                    match (i, c == &1) {
+                        (0, _) => format!("{}{}", sign, num(c)),
+                        (_, true) => format!("{}{}", sign, power(i)),
+                        (_, false) => format!("{}{}{}", sign, num(c), power(i))
+                    }
+                }, "<mn>0</mn>");
+                if order {
+                    if res != "<mn>0</mn>" { res += "<mo>+</mo>"; } else { res.clear(); }
+                    res += &format!("<mi>O</mi><mo>(</mo>{}<mo>)</mo>", power(n));
+                }
+                format!("<math>{}</math>", res)
+            }
+        }
+    }
+
+    // Joins the nonzero terms, term gets the sign, absolute coefficient, exponent and whether it comes first
+    fn signed_terms(&self, term: impl Fn(bool, &Rational, usize, bool) -> String, zero: &str) -> String {
+        let mut res = String::new();
+        for (i, c) in self.seq.iter().enumerate().filter(|(_, c)| **c != 0) {
+            res += &term(*c < 0, &c.clone().abs(), i, res.is_empty());
+        }
+        if res.is_empty() { zero.to_string() } else { res }
+    }
+
+    fn plain(&self, egf: bool) -> String {
+        let mut fact = Rational::from(1);
+        let mut res = String::new();
         for i in 0..self.seq.len() {
+            if i > 1 { fact *= i as u32; }
             if self.seq[i] == 0 { continue; }
-            if self.seq[i] > 0 && !fst { write!(f, "+")?; }
-            if &self.seq[i] == Rational::ONE && i != 0 { }
-            else if &self.seq[i] == Rational::NEG_ONE && i != 0 {
-                write!(f, "-")?;
+            let c = if egf { Rational::from(&self.seq[i] * &fact) } else { self.seq[i].clone() };
+            if c > 0 && !res.is_empty() { res += "+"; }
+            if c == 1 && i != 0 { }
+            else if c == -1 && i != 0 {
+                res += "-";
             } else {
-                write!(f, "{}", self.seq[i])?;
+                res += &c.to_string();
             }
-            if i == 1 { write!(f, "x")?; }
-            if i > 1 { write!(f, "x^{}", i)?; }
-            fst = false;
+            if i == 1 { res += "x"; }
+            if i > 1 { res += &format!("x^{}", i); }
+            if i > 1 && egf { res += &format!("/{}!", i); }
         }
-        if fst {
-            write!(f, "0")?;
-        }
-        Ok(())
+        if res.is_empty() { "0".to_string() } else { res }
+    }
+}
+
+impl std::fmt::Display for Series {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "{}", self.plain(false))
     }
 }
 